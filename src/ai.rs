@@ -1,5 +1,6 @@
 use crate::common::vector2ext::Vector2Ext;
 use crate::components::*;
+use crate::resources::{GameMode, NavGrid, WeaponList};
 use amethyst::{
     assets::PrefabData, core::math::*, core::Transform, derive::PrefabData, ecs::prelude::*, Error,
 };
@@ -20,6 +21,7 @@ pub enum AIState {
     Back(Entity),
     Right(Entity),
     Left(Entity),
+    Intercept(Entity),
     Neutral,
 }
 impl Default for AIState {
@@ -34,6 +36,7 @@ impl AI {
             AIState::Back(target) => Some(target),
             AIState::Right(target) => Some(target),
             AIState::Left(target) => Some(target),
+            AIState::Intercept(target) => Some(target),
             _ => None,
         }
     }
@@ -48,19 +51,41 @@ impl<'s> System<'s> for AISystem {
         ReadStorage<'s, Transform>,
         ReadStorage<'s, Rigidbody>,
         ReadStorage<'s, Bullet>,
+        ReadExpect<'s, WeaponList>,
+        ReadStorage<'s, Flag>,
+        ReadStorage<'s, Area>,
+        ReadExpect<'s, GameMode>,
+        Read<'s, NavGrid>,
+        ReadStorage<'s, StatusEffects>,
     );
 
     fn run(
         &mut self,
-        (entities, mut ai, mut players, transforms, rigidbodies, bullets): Self::SystemData,
+        (
+            entities,
+            mut ai,
+            mut players,
+            transforms,
+            rigidbodies,
+            bullets,
+            weapon_list,
+            flags,
+            areas,
+            mode,
+            nav_grid,
+            status_effects,
+        ): Self::SystemData,
     ) {
         for (entity, ai, transform, rigidbody) in
             (&entities, &mut ai, &transforms, &rigidbodies).join()
         {
             let mut rng = thread_rng();
 
-            let my_team = match players.get(entity) {
-                Some(player) => player.team,
+            let swapped_weapon = status_effects
+                .get(entity)
+                .and_then(|status| status.weapon());
+            let (my_team, my_weapon) = match players.get(entity) {
+                Some(player) => (player.team, swapped_weapon.unwrap_or(player.weapon)),
                 None => {
                     log::warn!("Failed to get player component");
                     continue;
@@ -81,6 +106,33 @@ impl<'s> System<'s> for AISystem {
                 }
             }
 
+            // flag carriers run home, the others sometimes go for the enemy flag
+            if mode.uses_flags() {
+                let carrying = flags.join().any(|flag| flag.carrier == Some(entity));
+                let objective = (&entities, &flags)
+                    .join()
+                    .find(|(_, flag)| {
+                        if carrying {
+                            flag.team == my_team
+                        } else {
+                            flag.team != my_team && flag.carrier.is_none()
+                        }
+                    })
+                    .map(|(objective, _)| objective);
+                if let Some(objective) = objective {
+                    if carrying || rng.gen_bool(0.005) {
+                        ai.state = AIState::Go(objective);
+                    }
+                }
+            }
+
+            // in the area modes they sometimes go and stand in the area
+            if mode.uses_area() && rng.gen_bool(0.005) {
+                if let Some((area, _)) = (&entities, &areas).join().next() {
+                    ai.state = AIState::Go(area);
+                }
+            }
+
             if rng.gen_bool(0.1) && rigidbody.velocity.norm() < 0.1 {
                 if let Some(target) = ai.target() {
                     if rng.gen_bool(0.5) {
@@ -91,18 +143,25 @@ impl<'s> System<'s> for AISystem {
                 }
             }
 
+            // shoot down incoming bullets if the weapon can, otherwise dodge them
+            let can_intercept = weapon_list.weapon(my_weapon).bullet_intercept != Intercept::None;
+            let dodge_range = if can_intercept { 80.0 } else { 40.0 };
             if rng.gen_bool(0.1) {
                 if let Some((next_target, _, _)) = (&entities, &bullets, &transforms)
                     .join()
                     .filter(|(_, bullet, _)| bullet.team != my_team)
                     .filter(|(_, _, transform)| {
-                        (transform.translation().xy() - my_pos).norm() < 40.0
+                        (transform.translation().xy() - my_pos).norm() < dodge_range
                     })
                     .min_by_key(|(_, _, transform)| {
                         (transform.translation().xy() - my_pos).norm() as i32
                     })
                 {
-                    ai.state = AIState::Back(next_target);
+                    ai.state = if can_intercept {
+                        AIState::Intercept(next_target)
+                    } else {
+                        AIState::Back(next_target)
+                    };
                 }
             }
 
@@ -122,7 +181,8 @@ impl<'s> System<'s> for AISystem {
                         ai.state = AIState::Neutral;
                         continue;
                     };
-                    let dist = target_pos - my_pos;
+                    // around the walls in the way
+                    let dist = nav_grid.waypoint(my_pos, target_pos) - my_pos;
                     let mut move_vec = normalize(dist);
 
                     if dist.norm() > 40.0 {
@@ -172,6 +232,17 @@ impl<'s> System<'s> for AISystem {
 
                     (move_vec, aim_vec, true)
                 }
+                AIState::Intercept(target) => {
+                    let target_pos = if let Some(t) = transforms.get(target) {
+                        t.translation().xy()
+                    } else {
+                        ai.state = AIState::Neutral;
+                        continue;
+                    };
+                    let aim_vec = normalize(target_pos - my_pos);
+
+                    (Vector2::zeros(), aim_vec, true)
+                }
                 _ => (Vector2::zeros(), Vector2::zeros(), false),
             };
 
@@ -17,6 +17,22 @@ pub struct Music {
     pub music: Repeat<SourceHandle>,
 }
 
+// volumes from 0.0 to 1.0, changed in the pause menu settings
+#[derive(Clone, Copy)]
+pub struct AudioSettings {
+    pub music: f32,
+    pub sound: f32,
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        AudioSettings {
+            music: 1.0,
+            sound: 1.0,
+        }
+    }
+}
+
 pub fn initialise_audio(world: &mut World) {
     let sounds = Sounds {
         array: vec![
@@ -31,6 +47,7 @@ pub fn initialise_audio(world: &mut World) {
     let music = Music { music };
     world.insert(sounds);
     world.insert(music);
+    world.insert(AudioSettings::default());
     world.insert(());
 }
 fn load(name: &str, world: &mut World) -> SourceHandle {
@@ -67,11 +84,12 @@ impl<'s> System<'s> for MyAudioSystem {
         Option<Read<'s, output::Output>>,
         ReadStorage<'s, Transform>,
         ReadStorage<'s, Camera>,
+        Read<'s, AudioSettings>,
     );
 
     fn run(
         &mut self,
-        (mut play_once, storage, sounds, output, transforms, cameras): Self::SystemData,
+        (mut play_once, storage, sounds, output, transforms, cameras, settings): Self::SystemData,
     ) {
         let player_pos = {
             if let Some((camera_transform, _)) = (&transforms, &cameras).join().next() {
@@ -89,8 +107,8 @@ impl<'s> System<'s> for MyAudioSystem {
             }
             let volume = volume * volume;
             if let Some(ref output) = output.as_ref() {
-                if let Some(sound) = storage.get(&sounds.array[p.sound]) {
-                    output.play_once(sound, p.volume * volume);
+                if let Some(sound) = sounds.array.get(p.sound).and_then(|s| storage.get(s)) {
+                    output.play_once(sound, p.volume * volume * settings.sound);
                 }
             }
         }
@@ -0,0 +1,118 @@
+// map building tool, run from the repository root:
+//   maptool from-grid <map.txt> [map.ron]   builds a map prefab from a text grid
+//   maptool to-grid <map.ron> [map.txt]     draws a map prefab back as a text grid
+//   maptool from-tiled <map.tmx> [map.ron]  converts a Tiled map (.tmx or .json)
+//   maptool check <map>                     reports what is wrong with a map
+//   maptool generate <seed> [map.ron]       generates a mirrored arena for two teams
+// without the output path the result is printed
+
+use amethyst::assets::Prefab;
+use std::{error::Error, fs, process};
+
+use tofu_on_fire::common::ronfile;
+use tofu_on_fire::mapdata::{self, Grid};
+use tofu_on_fire::prefab::MapPrefabData;
+use tofu_on_fire::resources::Teams;
+use tofu_on_fire::validation::{self, DataContext, Validate, Validator};
+
+const USAGE: &str = "usage:
+  maptool from-grid <map.txt> [map.ron]
+  maptool to-grid <map.ron> [map.txt]
+  maptool from-tiled <map.tmx|map.json> [map.ron]
+  maptool check <map.ron|map.tmx|map.json>
+  maptool generate <seed> [map.ron]";
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.len() < 2 || args.len() > 3 {
+        eprintln!("{}", USAGE);
+        process::exit(2);
+    }
+    let (input, out) = (args[1].as_str(), args.get(2).map(String::as_str));
+    let result = match args[0].as_str() {
+        "from-grid" => from_grid(input, out),
+        "to-grid" => to_grid(input, out),
+        "from-tiled" => from_tiled(input, out),
+        "check" if out.is_none() => check(input),
+        "generate" => generate(input, out),
+        _ => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+    if let Err(e) = result {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    }
+}
+
+fn output(path: Option<&str>, text: &str) -> Result<(), Box<dyn Error>> {
+    match path {
+        Some(path) => fs::write(path, text).map_err(|e| format!("{}: {}", path, e))?,
+        None => print!("{}", text),
+    }
+    Ok(())
+}
+
+fn from_grid(input: &str, out: Option<&str>) -> Result<(), Box<dyn Error>> {
+    let text = fs::read_to_string(input).map_err(|e| format!("{}: {}", input, e))?;
+    let grid = Grid::parse(&text).map_err(|e| format!("{}: {}", input, e))?;
+    // the team sprites for the spawn pads and flags
+    let teams: Teams = ronfile::load("resources/teams.ron")?;
+    let prefab = grid.to_prefab(&teams);
+    output(out, &mapdata::to_ron(&prefab)?)
+}
+
+fn to_grid(input: &str, out: Option<&str>) -> Result<(), Box<dyn Error>> {
+    let prefab: Prefab<MapPrefabData> = ronfile::load(input)?;
+    let (grid, warnings) = Grid::from_prefab(&prefab);
+    for warning in warnings {
+        eprintln!("warning: {}: {}", input, warning);
+    }
+    output(out, &grid.to_string())
+}
+
+fn from_tiled(input: &str, out: Option<&str>) -> Result<(), Box<dyn Error>> {
+    let text = fs::read_to_string(input).map_err(|e| format!("{}: {}", input, e))?;
+    let teams: Teams = ronfile::load("resources/teams.ron")?;
+    let (prefab, warnings) =
+        mapdata::import_tiled(&text, input, &teams).map_err(|e| format!("{}: {}", input, e))?;
+    for warning in warnings {
+        eprintln!("warning: {}: {}", input, warning);
+    }
+    output(out, &mapdata::to_ron(&prefab)?)
+}
+
+fn check(input: &str) -> Result<(), Box<dyn Error>> {
+    let teams: Teams = ronfile::load("resources/teams.ron")?;
+    let mut context = DataContext::new(validation::sprite_count()?);
+    context.set_teams(&teams);
+
+    let prefab: Prefab<MapPrefabData> = if mapdata::is_tiled(input) {
+        let text = fs::read_to_string(input).map_err(|e| format!("{}: {}", input, e))?;
+        mapdata::import_tiled(&text, input, &teams)
+            .map_err(|e| format!("{}: {}", input, e))?
+            .0
+    } else {
+        ronfile::load(input)?
+    };
+    let mut v = Validator::new(&context);
+    prefab.validate(&mut v);
+    for warning in v.warnings() {
+        eprintln!("warning: {}: {}", input, warning);
+    }
+    v.finish(input)?;
+    println!("{}: ok", input);
+    Ok(())
+}
+
+fn generate(seed: &str, out: Option<&str>) -> Result<(), Box<dyn Error>> {
+    let seed: u64 = seed
+        .parse()
+        .map_err(|e| format!("{}: not a seed: {}", seed, e))?;
+    let teams: Teams = ronfile::load("resources/teams.ron")?;
+    let grid = mapdata::generate(seed);
+    // the grid is easier to look at than the prefab
+    eprint!("{}", grid);
+    output(out, &mapdata::to_ron(&grid.to_prefab(&teams))?)
+}
@@ -40,9 +40,12 @@ impl<'a, 'b> SystemBundle<'a, 'b> for GameBundle {
                 .collide("Item", "Wall")
                 .collide("Particle", "Wall")
                 .trigger("Bullet", "Wall")
+                .trigger("Bullet", "Bullet")
                 .trigger("Player", "Bullet")
                 .trigger("Player", "Item")
-                .trigger("Player", "Area"),
+                .trigger("Player", "Area")
+                .trigger("Player", "Flag")
+                .trigger("Player", "Gadget"),
             "collision_system",
             &["rigidbody_system"],
         );
@@ -51,38 +54,103 @@ impl<'a, 'b> SystemBundle<'a, 'b> for GameBundle {
             "playable_system",
             &["input_system"],
         );
-        builder.add(AISystem, "ai_system", &[]);
+        builder.add(NavGridSystem::default(), "nav_grid_system", &[]);
+        builder.add(Pausable::new(AISystem), "ai_system", &["nav_grid_system"]);
         builder.add(
             Pausable::new(PlayerControlSystem),
             "player_control_system",
             &["playable_system", "ai_system"],
         );
         builder.add(
-            PlayerCollisionSystem,
+            Pausable::new(PlayerCollisionSystem),
             "player_collision_system",
             &["player_control_system"],
         );
         builder.add(
-            PlayerDeathSystem,
-            "player_death_system",
+            Pausable::new(GadgetSystem::default()),
+            "gadget_system",
+            &["player_collision_system"],
+        );
+        builder.add(
+            Pausable::new(StatusEffectSystem),
+            "status_effect_system",
+            &["gadget_system"],
+        );
+        builder.add(
+            Pausable::new(ExplosionSystem),
+            "explosion_system",
             &["player_collision_system"],
         );
-        builder.add(ShieldSystem, "shield_system", &["player_control_system"]);
+        builder.add(
+            Pausable::new(PlayerDeathSystem),
+            "player_death_system",
+            &["status_effect_system", "explosion_system"],
+        );
+        builder.add(
+            StatsSystem::default(),
+            "stats_system",
+            &["player_death_system"],
+        );
+        builder.add(
+            KillFeedSystem::default(),
+            "kill_feed_system",
+            &["player_death_system"],
+        );
+        builder.add(
+            Pausable::new(DeathmatchSystem::default()),
+            "deathmatch_system",
+            &["player_death_system"],
+        );
+        builder.add(
+            Pausable::new(FlagSystem),
+            "flag_system",
+            &["player_death_system"],
+        );
+        builder.add(
+            Pausable::new(ShieldSystem),
+            "shield_system",
+            &["player_control_system"],
+        );
+        builder.add(
+            BuffIconSystem,
+            "buff_icon_system",
+            &["player_control_system"],
+        );
         builder.add(ReticleSystem, "reticle_system", &["player_control_system"]);
         builder.add(
             Pausable::new(BulletSystem),
             "bullet_system",
             &["player_control_system"],
         );
+        builder.add(
+            Pausable::new(DestructibleSystem),
+            "destructible_system",
+            &["bullet_system", "explosion_system"],
+        );
         builder.add(
             CameraSystem::default(),
             "camera_system",
             &["player_control_system"],
         );
 
-        builder.add(ParticleSystem, "particle_system", &[]);
-        builder.add(ItemSystem, "item_system", &[]);
+        builder.add(Pausable::new(ParticleSystem), "particle_system", &[]);
+        builder.add(Pausable::new(ItemSystem), "item_system", &[]);
         builder.add(Pausable::new(AreaSystem::default()), "area_system", &[]);
+        builder.add(
+            Pausable::new(MatchClockSystem),
+            "match_clock_system",
+            &["deathmatch_system", "flag_system", "area_system"],
+        );
+        builder.add(
+            ObjectiveVisibilitySystem,
+            "objective_visibility_system",
+            &[],
+        );
+        builder.add(
+            ScoreTextSystem,
+            "score_text_system",
+            &["deathmatch_system", "flag_system", "area_system"],
+        );
 
         builder.add_barrier();
         builder.add(MyAudioSystem, "my_audio_system", &[]);
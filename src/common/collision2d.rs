@@ -9,6 +9,11 @@ use crate::common::vector2ext::Vector2Ext;
 
 use std::collections::{HashMap, HashSet};
 
+// the space the collision grid divides, colliders outside it all fall in its edge cells.
+// as wide as the built-in maps, 22 tiles from the outer wall to the outer wall
+pub const FIELD_WIDTH: f32 = 704.0;
+pub const FIELD_HEIGHT: f32 = 480.0;
+
 #[derive(Component, PrefabData, Deserialize, Serialize, Clone, Debug)]
 #[prefab(Component)]
 #[serde(default)]
@@ -95,7 +100,7 @@ impl Default for CollisionSystem {
             collide_entries: HashSet::new(),
             trigger_entries: HashSet::new(),
             bitsets: HashMap::new(),
-            qtg: QuadTreeGrid::new(4, 0.0, 0.0, 640.0, 480.0),
+            qtg: QuadTreeGrid::new(4, 0.0, 0.0, FIELD_WIDTH as f64, FIELD_HEIGHT as f64),
         }
     }
 }
@@ -150,7 +155,9 @@ impl<'s> System<'s> for CollisionSystem {
                 );
             }
 
-            self.bitsets.get_mut(&collider.tag).unwrap().add(ent.id());
+            if let Some(bitset) = self.bitsets.get_mut(&collider.tag) {
+                bitset.add(ent.id());
+            }
         }
 
         let entries = self.collide_entries.union(&self.trigger_entries);
@@ -161,6 +168,8 @@ impl<'s> System<'s> for CollisionSystem {
             let bitset_b = self.bitsets.get(tag_b).unwrap();
             let is_trigger = self.trigger_entries.contains(&entry);
             let is_collide = self.collide_entries.contains(&entry);
+            // entities of a tag paired with itself are added once and all pairs are checked
+            let same_tag = tag_a == tag_b;
 
             for (_, entity, collider, transform) in
                 (bitset_a, &entities, &colliders, &transforms).join()
@@ -173,19 +182,21 @@ impl<'s> System<'s> for CollisionSystem {
                     .add_entity(x - w / 2.0, y - h / 2.0, w, h, (entity, true));
             }
 
-            for (_, entity, collider, transform) in
-                (bitset_b, &entities, &colliders, &transforms).join()
-            {
-                let x = transform.translation().x as f64;
-                let y = transform.translation().y as f64;
-                let w = collider.width as f64;
-                let h = collider.height as f64;
-                self.qtg
-                    .add_entity(x - w / 2.0, y - h / 2.0, w, h, (entity, false));
+            if !same_tag {
+                for (_, entity, collider, transform) in
+                    (bitset_b, &entities, &colliders, &transforms).join()
+                {
+                    let x = transform.translation().x as f64;
+                    let y = transform.translation().y as f64;
+                    let w = collider.width as f64;
+                    let h = collider.height as f64;
+                    self.qtg
+                        .add_entity(x - w / 2.0, y - h / 2.0, w, h, (entity, false));
+                }
             }
 
             self.qtg.iter_entity_pair(|(ent_a, a), (ent_b, b)| {
-                if a == b {
+                if a == b && !same_tag {
                     return;
                 }
                 let (ent_a, ent_b) = if *a {
@@ -293,3 +304,33 @@ impl<'s> System<'s> for CollisionSystem {
         }
     }
 }
+
+// whether the segment from `from` to `to` passes through the rectangle
+pub fn segment_intersects_rect(
+    from: Vector2<f32>,
+    to: Vector2<f32>,
+    center: Vector2<f32>,
+    size: Vector2<f32>,
+) -> bool {
+    let min = center - size / 2.0;
+    let max = center + size / 2.0;
+    let dir = to - from;
+    let mut t_min = 0.0f32;
+    let mut t_max = 1.0f32;
+    for i in 0..2 {
+        if dir[i].abs() < std::f32::EPSILON {
+            if from[i] < min[i] || from[i] > max[i] {
+                return false;
+            }
+        } else {
+            let t1 = (min[i] - from[i]) / dir[i];
+            let t2 = (max[i] - from[i]) / dir[i];
+            t_min = t_min.max(t1.min(t2));
+            t_max = t_max.min(t1.max(t2));
+            if t_min > t_max {
+                return false;
+            }
+        }
+    }
+    true
+}
@@ -4,4 +4,5 @@ pub mod collision2d;
 pub mod pause;
 pub mod prefab;
 pub mod quadtreegrid;
+pub mod ronfile;
 pub mod vector2ext;
@@ -32,6 +32,12 @@ where
 {
     type SystemData = (T::SystemData, Read<'s, Pause>);
 
+    // the inner system may set up more than its data, like an event reader
+    fn setup(&mut self, world: &mut World) {
+        Self::SystemData::setup(world);
+        self.0.setup(world);
+    }
+
     fn run(&mut self, (inner, pause): Self::SystemData) {
         if pause.paused() {
             return;
@@ -0,0 +1,59 @@
+use serde::de::DeserializeOwned;
+use std::{fmt, fs, path::Path, time::SystemTime};
+
+#[derive(Debug)]
+pub enum RonError {
+    Io {
+        path: String,
+        error: std::io::Error,
+    },
+    Parse {
+        path: String,
+        line: usize,
+        col: usize,
+        message: String,
+    },
+}
+
+impl fmt::Display for RonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RonError::Io { path, error } => write!(f, "{}: {}", path, error),
+            RonError::Parse {
+                path,
+                line,
+                col,
+                message,
+            } => write!(f, "{} (line {}, column {}): {}", path, line, col, message),
+        }
+    }
+}
+
+impl std::error::Error for RonError {}
+
+pub fn load<T: DeserializeOwned>(path: impl AsRef<Path>) -> Result<T, RonError> {
+    let path = path.as_ref();
+    let text = fs::read_to_string(path).map_err(|error| RonError::Io {
+        path: path.display().to_string(),
+        error,
+    })?;
+    from_str(&text, &path.display().to_string())
+}
+
+pub fn load_bytes<T: DeserializeOwned>(bytes: &[u8], name: &str) -> Result<T, RonError> {
+    let text = String::from_utf8_lossy(bytes);
+    from_str(&text, name)
+}
+
+pub fn from_str<T: DeserializeOwned>(text: &str, name: &str) -> Result<T, RonError> {
+    ron::de::from_str(text).map_err(|e| RonError::Parse {
+        path: name.to_string(),
+        line: e.position.line,
+        col: e.position.col,
+        message: e.code.to_string(),
+    })
+}
+
+pub fn modified(path: impl AsRef<Path>) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
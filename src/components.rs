@@ -2,6 +2,8 @@ use amethyst::{assets::PrefabData, core::math::*, derive::PrefabData, ecs::prelu
 use serde_derive::{Deserialize, Serialize};
 use specs_derive::Component;
 
+use crate::resources::{GameMode, Weapon};
+
 pub use crate::common::collision2d::{ColliderResult, RectCollider, Rigidbody};
 
 #[derive(Component, PrefabData, Deserialize, Serialize, Clone, Debug)]
@@ -19,6 +21,12 @@ pub struct Player {
     pub input_shot: bool,
     #[serde(skip, default = "zero")]
     pub trigger_timer: u32,
+    // who damaged this tofu most recently, credited with the kill
+    #[serde(skip)]
+    pub last_hit: Option<Credit>,
+    // which of its team's bots it is, kept across respawns
+    #[serde(skip)]
+    pub bot_slot: u32,
 }
 impl Default for Player {
     fn default() -> Player {
@@ -30,14 +38,54 @@ impl Default for Player {
             input_aim: Vector2::zeros(),
             input_shot: false,
             trigger_timer: 0,
+            last_hit: None,
+            bot_slot: 0,
+        }
+    }
+}
+
+// who stats are kept for, a bot by its slot in the team
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Combatant {
+    Human { id: usize, team: u32 },
+    Bot { team: u32, slot: u32 },
+}
+impl Combatant {
+    pub fn of(player: &Player, playable: Option<&Playable>) -> Combatant {
+        match playable {
+            Some(playable) => Combatant::Human {
+                id: playable.id,
+                team: player.team,
+            },
+            None => Combatant::Bot {
+                team: player.team,
+                slot: player.bot_slot,
+            },
+        }
+    }
+
+    pub fn team(&self) -> u32 {
+        match *self {
+            Combatant::Human { team, .. } => team,
+            Combatant::Bot { team, .. } => team,
         }
     }
 }
 
+// attribution carried by bullets and explosions
+#[derive(Clone, Copy, Debug)]
+pub struct Credit {
+    pub combatant: Combatant,
+    pub weapon: usize,
+}
+
+// index into LocalPlayers of the human controlling this entity
 #[derive(Component, PrefabData, Deserialize, Serialize, Default, Clone, Debug)]
-#[storage(NullStorage)]
 #[prefab(Component)]
-pub struct Playable;
+#[serde(default)]
+pub struct Playable {
+    pub id: usize,
+}
 
 #[derive(Component, PrefabData, Deserialize, Serialize, Default, Clone, Debug)]
 #[storage(NullStorage)]
@@ -54,6 +102,12 @@ pub struct ReticleLine;
 #[prefab(Component)]
 pub struct Shield;
 
+// shows the item buffs the parent tofu has, one after another
+#[derive(Component, PrefabData, Deserialize, Serialize, Default, Clone, Debug)]
+#[storage(NullStorage)]
+#[prefab(Component)]
+pub struct BuffIcon;
+
 #[derive(Component, PrefabData, Deserialize, Serialize, Clone, Debug)]
 #[prefab(Component)]
 pub struct SpawnPoint {
@@ -65,12 +119,224 @@ pub struct SpawnPoint {
 #[prefab(Component)]
 pub struct Area;
 
+// route of the area in a two team match, from the first team's side to the second's
+#[derive(Component, PrefabData, Deserialize, Serialize, Clone, Debug)]
+#[prefab(Component)]
+pub struct AreaPath {
+    pub points: Vec<(f32, f32)>,
+    // a Catmull-Rom curve through the points instead of straight segments
+    #[serde(default)]
+    pub spline: bool,
+    // ratios along the path the area cannot be pushed back past once reached
+    #[serde(default)]
+    pub checkpoints: Vec<f32>,
+    #[serde(skip, default = "unlocked")]
+    pub locked: (f32, f32),
+    // lead of the first team a checkpoint keeps on top of the score
+    #[serde(skip, default = "zero")]
+    pub held: i32,
+}
+impl AreaPath {
+    pub fn new(points: Vec<(f32, f32)>) -> Self {
+        AreaPath {
+            points,
+            spline: false,
+            checkpoints: Vec::new(),
+            locked: unlocked(),
+            held: 0,
+        }
+    }
+
+    pub fn position(&self, ratio: f32) -> Vector2<f32> {
+        let points = self.polyline();
+        let total: f32 = points
+            .windows(2)
+            .map(|pair| (pair[1] - pair[0]).norm())
+            .sum();
+        let mut distance = total * ratio.max(0.0).min(1.0);
+        for pair in points.windows(2) {
+            let length = (pair[1] - pair[0]).norm();
+            if distance <= length && length > 0.0 {
+                return pair[0] + (pair[1] - pair[0]) * (distance / length);
+            }
+            distance -= length;
+        }
+        points.last().copied().unwrap_or_else(Vector2::zeros)
+    }
+
+    // locks the checkpoints the ratio has reached and keeps it within them
+    pub fn lock(&mut self, ratio: f32) -> f32 {
+        for &checkpoint in self.checkpoints.iter() {
+            if checkpoint > 0.5 && ratio >= checkpoint {
+                self.locked.0 = self.locked.0.max(checkpoint);
+            }
+            if checkpoint < 0.5 && ratio <= checkpoint {
+                self.locked.1 = self.locked.1.min(checkpoint);
+            }
+        }
+        ratio.max(self.locked.0).min(self.locked.1)
+    }
+
+    // what the checkpoints of the map's area path hold, a map has at most one
+    pub fn held_on(paths: &ReadStorage<'_, AreaPath>) -> i32 {
+        paths.join().map(|path| path.held).next().unwrap_or(0)
+    }
+
+    pub fn unlock(&mut self) {
+        self.locked = unlocked();
+        self.held = 0;
+    }
+
+    fn polyline(&self) -> Vec<Vector2<f32>> {
+        let points: Vec<Vector2<f32>> = self
+            .points
+            .iter()
+            .map(|&(x, y)| Vector2::new(x, y))
+            .collect();
+        if !self.spline || points.len() < 3 {
+            return points;
+        }
+        let mut curve = Vec::new();
+        for i in 0..points.len() - 1 {
+            let p0 = points[i.saturating_sub(1)];
+            let p1 = points[i];
+            let p2 = points[i + 1];
+            let p3 = points[(i + 2).min(points.len() - 1)];
+            for step in 0..SPLINE_STEPS {
+                let t = step as f32 / SPLINE_STEPS as f32;
+                let t2 = t * t;
+                let t3 = t2 * t;
+                curve.push(
+                    (p1 * 2.0
+                        + (p2 - p0) * t
+                        + (p0 * 2.0 - p1 * 5.0 + p2 * 4.0 - p3) * t2
+                        + (p1 * 3.0 - p0 - p2 * 3.0 + p3) * t3)
+                        * 0.5,
+                );
+            }
+        }
+        curve.extend(points.last());
+        curve
+    }
+}
+
+const SPLINE_STEPS: usize = 16;
+
+fn unlocked() -> (f32, f32) {
+    (0.0, 1.0)
+}
+
 #[derive(Component, PrefabData, Deserialize, Serialize, Clone, Debug)]
 #[prefab(Component)]
 pub struct AreaTarget {
     pub team: u32,
 }
 
+#[derive(Component, PrefabData, Deserialize, Serialize, Clone, Debug)]
+#[prefab(Component)]
+pub struct Flag {
+    pub team: u32,
+    #[serde(skip)]
+    pub carrier: Option<Entity>,
+    // where the flag is captured and returned to, taken from its first position
+    #[serde(skip)]
+    pub home: Option<Vector3<f32>>,
+    // frames since it was dropped away from home
+    #[serde(skip)]
+    pub dropped: u32,
+}
+impl Flag {
+    pub fn new(team: u32) -> Self {
+        Flag {
+            team,
+            carrier: None,
+            home: None,
+            dropped: 0,
+        }
+    }
+}
+
+// modes the map can be played in, the first one is the default
+#[derive(Component, PrefabData, Deserialize, Serialize, Clone, Debug)]
+#[prefab(Component)]
+pub struct MapInfo {
+    pub modes: Vec<GameMode>,
+}
+
+// a map wall that bullets and explosions wear down until it breaks
+#[derive(Component, PrefabData, Deserialize, Serialize, Clone, Debug)]
+#[prefab(Component)]
+pub struct Destructible {
+    pub hp: f32,
+    // shown in turn as the wall loses hp, the last one just before it breaks
+    #[serde(default)]
+    pub damage_sprites: Vec<usize>,
+    // particles of its sprite thrown around when it breaks
+    #[serde(default = "default_debris")]
+    pub debris: u32,
+    #[serde(skip, default = "zero")]
+    pub damage: f32,
+}
+impl Destructible {
+    pub fn hit(&mut self, damage: f32) {
+        self.damage += damage;
+    }
+
+    pub fn broken(&self) -> bool {
+        self.damage >= self.hp
+    }
+
+    // None while the wall still looks whole
+    pub fn damage_sprite(&self) -> Option<usize> {
+        let stages = self.damage_sprites.len();
+        let stage = (self.damage / self.hp * (stages + 1) as f32) as usize;
+        let stage = stage.min(stages).checked_sub(1)?;
+        self.damage_sprites.get(stage).copied()
+    }
+}
+
+fn default_debris() -> u32 {
+    8
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub enum GadgetKind {
+    // damage per tick to the players in it
+    DamageZone(f32),
+    // multiplies the move speed of the players in it by (1 - value)
+    SlowZone(f32),
+    // added to the acceleration of the players on it
+    SpeedPad(f32, f32),
+    // pushes the players on it away from its center
+    BouncePad(f32),
+    // sends the players on it to the other teleporter of the same channel
+    Teleporter(u32),
+}
+
+// map entities players trigger by touching them, with a "Gadget" collider
+#[derive(Component, PrefabData, Deserialize, Serialize, Clone, Debug)]
+#[prefab(Component)]
+pub struct Gadget {
+    pub kind: GadgetKind,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq)]
+pub enum Intercept {
+    // passes through enemy bullets
+    None,
+    // deals its damage to enemy bullets and is spent unless it pierces
+    Destroy,
+    // removes enemy bullets, losing their damage from its own hp
+    Absorb,
+    // sends enemy bullets back as its own team, losing their damage from its own hp
+    Reflect,
+}
+impl Default for Intercept {
+    fn default() -> Intercept {
+        Intercept::None
+    }
+}
+
 #[derive(Component, PrefabData, Deserialize, Serialize, Clone, Debug)]
 #[prefab(Component)]
 pub struct Bullet {
@@ -81,6 +347,18 @@ pub struct Bullet {
     pub knockback: f32,
     pub slowing: f32,
     pub pierce: bool,
+    #[serde(default)]
+    pub effects: Vec<StatusEffect>,
+    #[serde(default)]
+    pub hp: f32,
+    #[serde(default)]
+    pub intercept: Intercept,
+    #[serde(default)]
+    pub explosion: Option<Explosion>,
+    #[serde(skip)]
+    pub shooter: Option<Entity>,
+    #[serde(skip)]
+    pub credit: Option<Credit>,
 
     #[serde(skip, default = "zero")]
     pub timer_count: u32,
@@ -88,36 +366,250 @@ pub struct Bullet {
     pub reflect_count: u32,
 }
 impl Bullet {
-    pub fn new(
-        team: u32,
-        damage: f32,
-        timer_limit: u32,
-        reflect_limit: u32,
-        knockback: f32,
-        slowing: f32,
-        pierce: bool,
-    ) -> Bullet {
+    pub fn new(shooter: Entity, team: u32, credit: Credit, weapon: &Weapon) -> Bullet {
         Bullet {
             team,
-            damage,
-            timer_limit,
-            reflect_limit,
-            knockback,
-            slowing,
-            pierce,
+            damage: weapon.bullet_damage,
+            timer_limit: weapon.bullet_timer_limit,
+            reflect_limit: weapon.bullet_reflect_limit,
+            knockback: weapon.bullet_knockback,
+            slowing: weapon.bullet_slowing,
+            pierce: weapon.bullet_pierce,
+            effects: weapon.bullet_effects.clone(),
+            hp: weapon.bullet_hp,
+            intercept: weapon.bullet_intercept,
+            explosion: weapon.bullet_explosion.clone(),
+            shooter: Some(shooter),
+            credit: Some(credit),
             timer_count: 0,
             reflect_count: 0,
         }
     }
 }
 
+#[derive(Component, PrefabData, Deserialize, Serialize, Clone, Debug)]
+#[prefab(Component)]
+pub struct Explosion {
+    pub damage: f32,
+    pub radius: f32,
+    pub knockback: f32,
+
+    #[serde(skip, default = "zero")]
+    pub team: u32,
+    #[serde(skip)]
+    pub shooter: Option<Entity>,
+    #[serde(skip)]
+    pub credit: Option<Credit>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub enum StatusKind {
+    // multiplies move speed by (1 - value)
+    Slow(f32),
+    // damage per tick
+    DamageOverTime(f32),
+    // blocks movement and shooting
+    Stun,
+    // multiplies incoming damage by (1 + value)
+    Weakened(f32),
+    // ignores hits and cannot shoot, given on spawn
+    SpawnProtection,
+    // multiplies move speed by (1 + value)
+    SpeedBoost(f32),
+    // multiplies the damage of own bullets by (1 + value)
+    DamageBoost(f32),
+    // hp can go up to 100 + value
+    Overcharge(f32),
+    // multiplies the time between shots by (1 - value)
+    RapidFire(f32),
+    // shoots with this weapon of the WeaponList instead
+    Weapon(usize),
+}
+impl StatusKind {
+    // the ones items give, shown over the tofu
+    pub fn is_buff(&self) -> bool {
+        match self {
+            StatusKind::SpeedBoost(_)
+            | StatusKind::DamageBoost(_)
+            | StatusKind::Overcharge(_)
+            | StatusKind::RapidFire(_)
+            | StatusKind::Weapon(_) => true,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct StatusEffect {
+    pub kind: StatusKind,
+    pub duration: u32,
+}
+
+#[derive(Component, Default, Clone, Debug)]
+pub struct StatusEffects {
+    pub effects: Vec<StatusEffect>,
+}
+impl StatusEffects {
+    pub fn add(&mut self, effect: StatusEffect) {
+        self.effects.push(effect);
+    }
+
+    // keeps an effect going without stacking it, like a zone the player stays in
+    pub fn refresh(&mut self, effect: StatusEffect) {
+        match self.effects.iter_mut().find(|e| e.kind == effect.kind) {
+            Some(existing) => existing.duration = existing.duration.max(effect.duration),
+            None => self.add(effect),
+        }
+    }
+
+    pub fn tick(&mut self) {
+        for effect in self.effects.iter_mut() {
+            effect.duration = effect.duration.saturating_sub(1);
+        }
+        self.effects.retain(|effect| effect.duration > 0);
+    }
+
+    pub fn stunned(&self) -> bool {
+        self.effects
+            .iter()
+            .any(|effect| effect.kind == StatusKind::Stun)
+    }
+
+    // ticks of spawn protection left
+    pub fn protection(&self) -> Option<u32> {
+        self.effects
+            .iter()
+            .filter(|effect| effect.kind == StatusKind::SpawnProtection)
+            .map(|effect| effect.duration)
+            .max()
+    }
+
+    pub fn move_multiplier(&self) -> f32 {
+        self.effects
+            .iter()
+            .map(|effect| match effect.kind {
+                StatusKind::Slow(value) => (1.0 - value).max(0.0),
+                StatusKind::SpeedBoost(value) => 1.0 + value,
+                _ => 1.0,
+            })
+            .product()
+    }
+
+    // for the damage the player deals
+    pub fn attack_multiplier(&self) -> f32 {
+        1.0 + self
+            .effects
+            .iter()
+            .map(|effect| match effect.kind {
+                StatusKind::DamageBoost(value) => value,
+                _ => 0.0,
+            })
+            .sum::<f32>()
+    }
+
+    pub fn rate_multiplier(&self) -> f32 {
+        self.effects
+            .iter()
+            .map(|effect| match effect.kind {
+                StatusKind::RapidFire(value) => (1.0 - value).max(0.0),
+                _ => 1.0,
+            })
+            .product()
+    }
+
+    pub fn max_hp(&self) -> f32 {
+        100.0
+            + self
+                .effects
+                .iter()
+                .map(|effect| match effect.kind {
+                    StatusKind::Overcharge(value) => value,
+                    _ => 0.0,
+                })
+                .fold(0.0, f32::max)
+    }
+
+    // the weapon picked up last
+    pub fn weapon(&self) -> Option<usize> {
+        self.effects
+            .iter()
+            .rev()
+            .filter_map(|effect| match effect.kind {
+                StatusKind::Weapon(index) => Some(index),
+                _ => None,
+            })
+            .next()
+    }
+
+    pub fn damage_multiplier(&self) -> f32 {
+        1.0 + self
+            .effects
+            .iter()
+            .map(|effect| match effect.kind {
+                StatusKind::Weakened(value) => value,
+                _ => 0.0,
+            })
+            .sum::<f32>()
+    }
+
+    pub fn damage_per_tick(&self) -> f32 {
+        self.effects
+            .iter()
+            .map(|effect| match effect.kind {
+                StatusKind::DamageOverTime(value) => value,
+                _ => 0.0,
+            })
+            .sum()
+    }
+}
+
 #[derive(Component, PrefabData, Deserialize, Serialize, Clone, Debug)]
 #[prefab(Component)]
 pub struct Item {
-    pub hp: f32,
+    pub kind: ItemKind,
+    // ticks the effect lasts, heals are instant
+    #[serde(default)]
+    pub duration: u32,
     pub timer: i32,
 }
 
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub enum ItemKind {
+    // hp restored, up to 100
+    Heal(f32),
+    // multiplies move speed by (1 + value)
+    SpeedBoost(f32),
+    // multiplies the damage of own bullets by (1 + value)
+    DamageBoost(f32),
+    // hp added beyond 100, up to 100 + value
+    Overcharge(f32),
+    // multiplies the time between shots by (1 - value)
+    RapidFire(f32),
+    // shoots with this weapon of the WeaponList instead
+    WeaponSwap(usize),
+}
+impl ItemKind {
+    // the effect the item gives for its duration
+    pub fn status(&self) -> Option<StatusKind> {
+        match *self {
+            ItemKind::Heal(_) => None,
+            ItemKind::SpeedBoost(value) => Some(StatusKind::SpeedBoost(value)),
+            ItemKind::DamageBoost(value) => Some(StatusKind::DamageBoost(value)),
+            ItemKind::Overcharge(value) => Some(StatusKind::Overcharge(value)),
+            ItemKind::RapidFire(value) => Some(StatusKind::RapidFire(value)),
+            ItemKind::WeaponSwap(index) => Some(StatusKind::Weapon(index)),
+        }
+    }
+
+    // hp given on pickup
+    pub fn hp(&self) -> f32 {
+        match *self {
+            ItemKind::Heal(hp) | ItemKind::Overcharge(hp) => hp,
+            _ => 0.0,
+        }
+    }
+}
+
 #[derive(Component, PrefabData, Deserialize, Serialize, Clone, Debug)]
 #[prefab(Component)]
 pub struct Particle {
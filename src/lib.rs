@@ -0,0 +1,24 @@
+pub mod ai;
+pub mod audio;
+pub mod bundle;
+pub mod common;
+pub mod components;
+pub mod mapdata;
+pub mod prefab;
+pub mod resources;
+pub mod state;
+pub mod systems;
+pub mod validation;
+
+#[macro_export]
+macro_rules! skip_fail {
+    ($res:expr) => {
+        match $res {
+            Ok(val) => val,
+            Err(e) => {
+                log::warn!("{} (L{})", e, line!());
+                continue;
+            }
+        }
+    };
+}
@@ -15,28 +15,7 @@ use amethyst::{
     window::DisplayConfig,
 };
 
-mod ai;
-mod audio;
-mod bundle;
-mod common;
-mod components;
-mod prefab;
-mod resources;
-mod state;
-mod systems;
-
-#[macro_export]
-macro_rules! skip_fail {
-    ($res:expr) => {
-        match $res {
-            Ok(val) => val,
-            Err(e) => {
-                log::warn!("{} (L{})", e, line!());
-                continue;
-            }
-        }
-    };
-}
+use tofu_on_fire::{audio, bundle, state};
 
 fn main() -> amethyst::Result<()> {
     amethyst::start_logger(Default::default());
@@ -0,0 +1,181 @@
+use amethyst::assets::Prefab;
+
+use super::grid::{add_objects, Object};
+use super::{
+    area, area_target, default_modes, flag, map_prefab, spawn_point, wall, wall_tiles, TileRect,
+    TILE, WALL_SPRITE,
+};
+use crate::prefab::MapPrefabData;
+use crate::resources::Teams;
+
+// objects snap to half tiles, so a spawn point can sit between two of them
+pub const SNAP: f32 = TILE / 2.0;
+// how far from its center an object can be picked up
+const PICK_RANGE: f32 = 16.0;
+
+// a map the way the editor changes it
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct MapDocument {
+    // the tiles and the sprite of each wall
+    pub walls: Vec<(TileRect, usize)>,
+    pub objects: Vec<(Object, f32, f32)>,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Piece {
+    Wall(usize),
+    Object(usize),
+}
+
+impl MapDocument {
+    // what the editor cannot change is left out, with a warning for each
+    pub fn from_prefab(prefab: &Prefab<MapPrefabData>) -> (MapDocument, Vec<String>) {
+        let (document, _, warnings) = import(prefab);
+        (document, warnings)
+    }
+
+    pub fn to_prefab(&self, teams: &Teams) -> Prefab<MapPrefabData> {
+        let mut prefab = map_prefab();
+        for &(rect, sprite) in self.walls.iter() {
+            prefab.add(None, Some(wall(rect, sprite)));
+        }
+        add_objects(&mut prefab, &self.objects, teams);
+        prefab
+    }
+
+    // what is shown while editing, every spawn point with a pad so each one can be seen
+    pub fn view(&self, teams: &Teams) -> Vec<MapPrefabData> {
+        let walls = self.walls.iter().map(|&(rect, sprite)| wall(rect, sprite));
+        let objects = self.objects.iter().map(|&(object, x, y)| match object {
+            Object::Spawn(team) => spawn_point(x, y, team, Some(teams)),
+            Object::Area => area(x, y, None, teams),
+            Object::Target(team) => area_target(x, y, team),
+            Object::Flag(team) => flag(x, y, team, teams),
+        });
+        walls.chain(objects).collect()
+    }
+
+    // objects come first, they are drawn over the walls
+    pub fn piece_at(&self, x: f32, y: f32) -> Option<Piece> {
+        let object = self
+            .objects
+            .iter()
+            .rposition(|&(_, ox, oy)| (ox - x).abs() <= PICK_RANGE && (oy - y).abs() <= PICK_RANGE);
+        if let Some(i) = object {
+            return Some(Piece::Object(i));
+        }
+        self.walls
+            .iter()
+            .rposition(|(rect, _)| {
+                let (left, bottom) = (rect.x as f32 * TILE, rect.y as f32 * TILE);
+                let (right, top) = (left + rect.w as f32 * TILE, bottom + rect.h as f32 * TILE);
+                left <= x && x < right && bottom <= y && y < top
+            })
+            .map(Piece::Wall)
+    }
+
+    pub fn remove(&mut self, piece: Piece) {
+        match piece {
+            Piece::Wall(i) => {
+                self.walls.remove(i);
+            }
+            Piece::Object(i) => {
+                self.objects.remove(i);
+            }
+        }
+    }
+}
+
+// rounded to the nearest multiple of the step
+pub fn snap(value: f32, step: f32) -> f32 {
+    (value / step).round() * step
+}
+
+// reads a map prefab back as walls and objects, shared by the editor and the grid,
+// with the entity each object came from and a warning for everything that is lost
+pub(super) fn import(prefab: &Prefab<MapPrefabData>) -> (MapDocument, Vec<usize>, Vec<String>) {
+    let mut document = MapDocument::default();
+    let mut sources = Vec::new();
+    let mut warnings = Vec::new();
+    let mut modes = None;
+
+    for (i, entity) in prefab.entities().enumerate() {
+        let data = match entity.data() {
+            Some(data) => data,
+            None => continue,
+        };
+        if let Some(info) = &data.map_info {
+            modes = Some(&info.modes);
+        }
+        if let Some(path) = &data.area_path {
+            if path.spline || !path.checkpoints.is_empty() || path.points.len() > 2 {
+                warnings.push(format!(
+                    "entities[{}].area_path: only a straight path between the targets is kept",
+                    i
+                ));
+            }
+        }
+        let translation = match &data.transform {
+            Some(transform) => *transform.translation(),
+            None if data.map_info.is_some() => continue,
+            None => {
+                warnings.push(format!("entities[{}]: has no transform, left out", i));
+                continue;
+            }
+        };
+        let (x, y) = (translation.x, translation.y);
+
+        if let Some(object) = Object::of(data) {
+            document.objects.push((object, x, y));
+            sources.push(i);
+            continue;
+        }
+        match &data.collider {
+            Some(collider) if collider.tag == "Wall" => {
+                let (rect, snapped) = wall_tiles(x, y, collider);
+                if snapped {
+                    warnings.push(format!(
+                        "entities[{}]: the wall was snapped to the tiles",
+                        i
+                    ));
+                }
+                if data.destructible.is_some() {
+                    warnings.push(format!("entities[{}]: the wall no longer breaks", i));
+                }
+                let sprite = data
+                    .sprite
+                    .as_ref()
+                    .map_or(WALL_SPRITE, |sprite| sprite.sprite_number);
+                document.walls.extend(rect.map(|rect| (rect, sprite)));
+            }
+            _ => warnings.push(format!("entities[{}]: left out", i)),
+        }
+    }
+
+    // the modes are worked out again from the objects when the map is built
+    let has = |wanted: Object| {
+        document
+            .objects
+            .iter()
+            .any(|&(object, _, _)| object == wanted)
+    };
+    let has_flags = document.objects.iter().any(|&(object, _, _)| match object {
+        Object::Flag(_) => true,
+        _ => false,
+    });
+    let rebuilt = default_modes(
+        has(Object::Area),
+        has(Object::Target(0)) && has(Object::Target(1)),
+        has_flags,
+    );
+    if let Some(modes) = modes {
+        if *modes != rebuilt {
+            warnings.push(format!(
+                "map_info: the modes {:?} become {:?}",
+                modes, rebuilt
+            ));
+        }
+    }
+
+    (document, sources, warnings)
+}
@@ -0,0 +1,190 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::collections::VecDeque;
+
+use super::Grid;
+
+// inside the collision grid's 704x480 in tiles, so no wall is ever outside of it
+const WIDTH: usize = 20;
+const HEIGHT: usize = 15;
+// the row the area moves along, counted from the top like the grid
+const TRACK: usize = HEIGHT / 2;
+// wall blocks scattered over the first team's half before it is mirrored
+const MIN_BLOCKS: usize = 6;
+const MAX_BLOCKS: usize = 12;
+const MAX_BLOCK_SIZE: usize = 3;
+
+// columns of the first team's objects, the second team's are mirrored
+const SPAWN_COLUMNS: (usize, usize) = (1, 2);
+const FLAG_COLUMN: usize = 3;
+const TARGET_COLUMN: usize = 5;
+
+// a map for two teams, the second team's half mirrors the first one.
+// the same seed always makes the same map
+pub fn generate(seed: u64) -> Grid {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut solid = vec![vec![false; WIDTH]; HEIGHT];
+
+    for (y, row) in solid.iter_mut().enumerate() {
+        for (x, cell) in row.iter_mut().enumerate() {
+            *cell = x == 0 || y == 0 || x == WIDTH - 1 || y == HEIGHT - 1;
+        }
+    }
+
+    for _ in 0..rng.gen_range(MIN_BLOCKS, MAX_BLOCKS + 1) {
+        let w = rng.gen_range(1, MAX_BLOCK_SIZE + 1);
+        let h = rng.gen_range(1, MAX_BLOCK_SIZE + 1);
+        let x = rng.gen_range(1, WIDTH / 2 - w + 1);
+        let y = rng.gen_range(1, HEIGHT - 1 - h + 1);
+        let cells = (y..y + h).flat_map(|y| (x..x + w).map(move |x| (x, y)));
+        if cells.clone().any(|(x, y)| kept_clear(x, y)) {
+            continue;
+        }
+        for (x, y) in cells {
+            solid[y][x] = true;
+        }
+    }
+
+    for row in solid.iter_mut() {
+        for x in WIDTH / 2..WIDTH {
+            row[x] = row[WIDTH - 1 - x];
+        }
+    }
+
+    // the track joins every object, floor that cannot reach it is filled in
+    let mut reached = vec![vec![false; WIDTH]; HEIGHT];
+    let mut queue = VecDeque::new();
+    reached[TRACK][WIDTH / 2] = true;
+    queue.push_back((WIDTH / 2, TRACK));
+    while let Some((x, y)) = queue.pop_front() {
+        for &(nx, ny) in [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)].iter() {
+            if !solid[ny][nx] && !reached[ny][nx] {
+                reached[ny][nx] = true;
+                queue.push_back((nx, ny));
+            }
+        }
+    }
+
+    let mut grid = Grid::new(WIDTH, HEIGHT);
+    for (y, row) in grid.rows.iter_mut().enumerate() {
+        for (x, tile) in row.iter_mut().enumerate() {
+            if !reached[y][x] {
+                *tile = '#';
+            }
+        }
+    }
+
+    let mirror = |x: usize| WIDTH - 1 - x;
+    for y in TRACK - 1..=TRACK + 1 {
+        for &x in [SPAWN_COLUMNS.0, SPAWN_COLUMNS.1].iter() {
+            grid.rows[y][x] = '0';
+            grid.rows[y][mirror(x)] = '1';
+        }
+    }
+    let track = &mut grid.rows[TRACK];
+    track[FLAG_COLUMN] = 'A';
+    track[mirror(FLAG_COLUMN)] = 'B';
+    track[TARGET_COLUMN] = 'a';
+    track[mirror(TARGET_COLUMN)] = 'b';
+    track[WIDTH / 2 - 1] = '@';
+    track[WIDTH / 2] = '@';
+
+    grid
+}
+
+// the spawn points, the track and the tiles around the area stay open
+fn kept_clear(x: usize, y: usize) -> bool {
+    let near_track = y + 1 >= TRACK && y <= TRACK + 1;
+    y == TRACK || near_track && (x <= TARGET_COLUMN || x + 2 >= WIDTH / 2)
+}
+
+#[cfg(test)]
+mod tests {
+    use amethyst::core::math::Vector2;
+
+    use super::*;
+    use crate::common::ronfile;
+    use crate::resources::{NavGrid, NavRect, Teams};
+    use crate::validation::{DataContext, Validate, Validator};
+
+    const SEEDS: std::ops::Range<u64> = 0..50;
+
+    fn teams() -> Teams {
+        ronfile::from_str(include_str!("../../resources/teams.ron"), "teams.ron").unwrap()
+    }
+
+    // the tile the mirrored one should be, the second team's objects for the first's
+    fn mirrored(tile: char) -> char {
+        match tile {
+            '0' => '1',
+            '1' => '0',
+            'A' => 'B',
+            'B' => 'A',
+            'a' => 'b',
+            'b' => 'a',
+            tile => tile,
+        }
+    }
+
+    #[test]
+    fn halves_mirror_each_other() {
+        for seed in SEEDS {
+            let grid = generate(seed);
+            assert_eq!((grid.width(), grid.height()), (WIDTH, HEIGHT));
+            for (y, row) in grid.rows.iter().enumerate() {
+                for x in 0..WIDTH {
+                    assert_eq!(
+                        row[x],
+                        mirrored(row[WIDTH - 1 - x]),
+                        "seed {}, column {}, line {}",
+                        seed,
+                        x + 1,
+                        y + 1
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn maps_are_valid() {
+        let teams = teams();
+        let mut context = DataContext::new(64);
+        context.set_teams(&teams);
+        for seed in SEEDS {
+            let prefab = generate(seed).to_prefab(&teams);
+            let mut v = Validator::new(&context);
+            prefab.validate(&mut v);
+            assert!(v.problems().is_empty(), "seed {}: {:?}", seed, v.problems());
+        }
+    }
+
+    #[test]
+    fn spawn_points_reach_the_area() {
+        let teams = teams();
+        for seed in SEEDS {
+            let prefab = generate(seed).to_prefab(&teams);
+            let mut walls: Vec<NavRect> = Vec::new();
+            let mut spawns = Vec::new();
+            let mut area = None;
+            for data in prefab.entities().filter_map(|entity| entity.data()) {
+                let position = match &data.transform {
+                    Some(transform) => transform.translation().xy(),
+                    None => continue,
+                };
+                if let Some(collider) = data.collider.as_ref().filter(|c| c.tag == "Wall") {
+                    walls.push((position, Vector2::new(collider.width, collider.height)));
+                }
+                if data.spawn_point.is_some() {
+                    spawns.push(position);
+                }
+                if data.area.is_some() {
+                    area = Some(position);
+                }
+            }
+            let area = area.unwrap();
+            assert_eq!(spawns.len(), 6, "seed {}", seed);
+            let reaches = NavGrid::new(&walls, &[]).reaches(area, &spawns);
+            assert!(reaches.iter().all(|&r| r), "seed {}: {:?}", seed, reaches);
+        }
+    }
+}
@@ -0,0 +1,420 @@
+use amethyst::assets::Prefab;
+use std::fmt;
+
+use super::edit::{import, MapDocument};
+use super::{
+    area, area_target, default_modes, flag, map_info, merge_walls, spawn_point, TileRect, TILE,
+    WALL_SPRITE,
+};
+use crate::components::AreaPath;
+use crate::prefab::MapPrefabData;
+use crate::resources::Teams;
+
+// one character per tile, the first line is the top of the map
+// '#' wall, '.' or ' ' floor, '@' the area,
+// '0'-'9' spawn points, 'a'-'j' area targets and 'A'-'J' flags of the teams.
+// a horizontal run of the same character is a single object placed at its middle,
+// so "00" puts a spawn point on the line between two tiles
+const FLOOR: char = '.';
+const WALL: char = '#';
+const AREA: char = '@';
+const MAX_TEAMS: u32 = 10;
+
+// what a map has besides walls, placed by its center
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Object {
+    Area,
+    Spawn(u32),
+    Target(u32),
+    Flag(u32),
+}
+
+impl Object {
+    fn from_char(c: char) -> Option<Object> {
+        match c {
+            AREA => Some(Object::Area),
+            '0'..='9' => Some(Object::Spawn(c as u32 - '0' as u32)),
+            'a'..='j' => Some(Object::Target(c as u32 - 'a' as u32)),
+            'A'..='J' => Some(Object::Flag(c as u32 - 'A' as u32)),
+            _ => None,
+        }
+    }
+
+    pub(super) fn of(data: &MapPrefabData) -> Option<Object> {
+        if let Some(spawn_point) = &data.spawn_point {
+            Some(Object::Spawn(spawn_point.team))
+        } else if data.area.is_some() {
+            Some(Object::Area)
+        } else if let Some(target) = &data.area_target {
+            Some(Object::Target(target.team))
+        } else if let Some(flag) = &data.flag {
+            Some(Object::Flag(flag.team))
+        } else {
+            None
+        }
+    }
+
+    fn to_char(self) -> Option<char> {
+        let team_char = |base: char, team: u32| {
+            if team < MAX_TEAMS {
+                std::char::from_u32(base as u32 + team)
+            } else {
+                None
+            }
+        };
+        match self {
+            Object::Area => Some(AREA),
+            Object::Spawn(team) => team_char('0', team),
+            Object::Target(team) => team_char('a', team),
+            Object::Flag(team) => team_char('A', team),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct GridError {
+    pub line: usize,
+    pub col: usize,
+    pub message: String,
+}
+
+impl fmt::Display for GridError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.col, self.message
+        )
+    }
+}
+
+impl std::error::Error for GridError {}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Grid {
+    // rows[0] is the top of the map
+    pub rows: Vec<Vec<char>>,
+}
+
+impl Grid {
+    pub fn new(width: usize, height: usize) -> Self {
+        Grid {
+            rows: vec![vec![FLOOR; width]; height],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.rows.iter().map(|row| row.len()).max().unwrap_or(0)
+    }
+
+    pub fn height(&self) -> usize {
+        self.rows.len()
+    }
+
+    // lines starting with "//" are comments
+    pub fn parse(text: &str) -> Result<Grid, GridError> {
+        let mut rows = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim_end();
+            if line.starts_with("//") {
+                continue;
+            }
+            let mut row = Vec::new();
+            for (j, c) in line.chars().enumerate() {
+                let c = if c == ' ' { FLOOR } else { c };
+                if c != FLOOR && c != WALL && Object::from_char(c).is_none() {
+                    return Err(GridError {
+                        line: i + 1,
+                        col: j + 1,
+                        message: format!("unknown tile '{}'", c),
+                    });
+                }
+                row.push(c);
+            }
+            rows.push(row);
+        }
+        // blank lines around the map are not part of it
+        while rows.last().map_or(false, |row| row.is_empty()) {
+            rows.pop();
+        }
+        while rows.first().map_or(false, |row| row.is_empty()) {
+            rows.remove(0);
+        }
+        if rows.is_empty() {
+            return Err(GridError {
+                line: 1,
+                col: 1,
+                message: "the map has no tiles".to_string(),
+            });
+        }
+
+        let mut grid = Grid { rows };
+        let width = grid.width();
+        for row in grid.rows.iter_mut() {
+            row.resize(width, FLOOR);
+        }
+        Ok(grid)
+    }
+
+    // y is counted from the bottom like the game does
+    fn get(&self, x: usize, y: usize) -> char {
+        let height = self.height();
+        self.rows[height - 1 - y].get(x).copied().unwrap_or(FLOOR)
+    }
+
+    fn set(&mut self, x: usize, y: usize, c: char) {
+        let height = self.height();
+        self.rows[height - 1 - y][x] = c;
+    }
+
+    // the objects with their positions in pixels, bottom to top and left to right
+    fn objects(&self) -> Vec<(Object, f32, f32)> {
+        let mut objects = Vec::new();
+        for y in 0..self.height() {
+            let mut x = 0;
+            while x < self.width() {
+                let c = self.get(x, y);
+                let start = x;
+                while x < self.width() && self.get(x, y) == c {
+                    x += 1;
+                }
+                if let Some(object) = Object::from_char(c) {
+                    let center_x = (start + x) as f32 * TILE / 2.0;
+                    let center_y = y as f32 * TILE + TILE / 2.0;
+                    objects.push((object, center_x, center_y));
+                }
+            }
+        }
+        objects
+    }
+
+    pub fn to_prefab(&self, teams: &Teams) -> Prefab<MapPrefabData> {
+        let solid: Vec<Vec<bool>> = (0..self.height())
+            .map(|y| (0..self.width()).map(|x| self.get(x, y) == WALL).collect())
+            .collect();
+        let document = MapDocument {
+            walls: merge_walls(&solid)
+                .into_iter()
+                .map(|rect| (rect, WALL_SPRITE))
+                .collect(),
+            objects: self.objects(),
+        };
+        document.to_prefab(teams)
+    }
+
+    // draws a map prefab back as a grid, with what could not be drawn exactly
+    pub fn from_prefab(prefab: &Prefab<MapPrefabData>) -> (Grid, Vec<String>) {
+        let (document, sources, mut warnings) = import(prefab);
+        let walls: Vec<TileRect> = document.walls.iter().map(|&(rect, _)| rect).collect();
+        let mut objects = Vec::new();
+        for (&(object, x, y), &i) in document.objects.iter().zip(sources.iter()) {
+            match object.to_char() {
+                Some(c) => objects.push((i, c, x, y)),
+                None => warnings.push(format!(
+                    "entities[{}]: only teams 0 to {} can be drawn",
+                    i,
+                    MAX_TEAMS - 1
+                )),
+            }
+        }
+
+        // an object on a tile line takes the two tiles around it
+        let cells = |x: f32, y: f32| {
+            let half = (x / (TILE / 2.0)).round().max(1.0) as usize;
+            let xs = if half % 2 == 1 {
+                half / 2..half / 2 + 1
+            } else {
+                half / 2 - 1..half / 2 + 1
+            };
+            (xs, (y / TILE).max(0.0) as usize)
+        };
+
+        let mut width = 0;
+        let mut height = 0;
+        for rect in walls.iter() {
+            width = width.max(rect.x + rect.w);
+            height = height.max(rect.y + rect.h);
+        }
+        for &(_, _, x, y) in objects.iter() {
+            let (xs, row) = cells(x, y);
+            width = width.max(xs.end);
+            height = height.max(row + 1);
+        }
+
+        let mut grid = Grid::new(width, height);
+        for rect in walls.iter() {
+            for y in rect.y..rect.y + rect.h {
+                for x in rect.x..rect.x + rect.w {
+                    grid.set(x, y, WALL);
+                }
+            }
+        }
+        for &(i, c, x, y) in objects.iter() {
+            let (xs, row) = cells(x, y);
+            let center_x = (xs.start + xs.end) as f32 * TILE / 2.0;
+            let center_y = row as f32 * TILE + TILE / 2.0;
+            if center_x != x || center_y != y {
+                warnings.push(format!(
+                    "entities[{}]: moved from ({}, {}) to ({}, {})",
+                    i, x, y, center_x, center_y
+                ));
+            }
+            for x in xs {
+                if grid.get(x, row) == FLOOR {
+                    grid.set(x, row, c);
+                } else {
+                    warnings.push(format!(
+                        "entities[{}]: the tile at column {}, line {} is taken by '{}'",
+                        i,
+                        x + 1,
+                        height - row,
+                        grid.get(x, row)
+                    ));
+                }
+            }
+        }
+
+        (grid, warnings)
+    }
+}
+
+impl fmt::Display for Grid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in self.rows.iter() {
+            writeln!(f, "{}", row.iter().collect::<String>())?;
+        }
+        Ok(())
+    }
+}
+
+// the objects with the pads, the area path and the modes that go with them
+pub(super) fn add_objects(
+    prefab: &mut Prefab<MapPrefabData>,
+    objects: &[(Object, f32, f32)],
+    teams: &Teams,
+) {
+    let find = |wanted: Object| {
+        objects
+            .iter()
+            .find(|(object, _, _)| *object == wanted)
+            .map(|&(_, x, y)| (x, y))
+    };
+
+    // the pad is drawn on the spawn point closest to the middle of the team's ones
+    for team in 0..MAX_TEAMS {
+        let spawns: Vec<(f32, f32)> = objects
+            .iter()
+            .filter(|(object, _, _)| *object == Object::Spawn(team))
+            .map(|&(_, x, y)| (x, y))
+            .collect();
+        if spawns.is_empty() {
+            continue;
+        }
+        let count = spawns.len() as f32;
+        let mean_x = spawns.iter().map(|p| p.0).sum::<f32>() / count;
+        let mean_y = spawns.iter().map(|p| p.1).sum::<f32>() / count;
+        let distance = |&(x, y): &(f32, f32)| (x - mean_x).powi(2) + (y - mean_y).powi(2);
+        let pad = (0..spawns.len())
+            .min_by(|&a, &b| {
+                distance(&spawns[a])
+                    .partial_cmp(&distance(&spawns[b]))
+                    .unwrap()
+            })
+            .unwrap_or(0);
+        prefab.add(
+            None,
+            Some(spawn_point(spawns[pad].0, spawns[pad].1, team, Some(teams))),
+        );
+        for (i, &(x, y)) in spawns.iter().enumerate() {
+            if i != pad {
+                prefab.add(None, Some(spawn_point(x, y, team, None)));
+            }
+        }
+    }
+
+    // a straight path from the first team's target to the second's
+    let path = match (find(Object::Target(0)), find(Object::Target(1))) {
+        (Some(from), Some(to)) => Some(AreaPath::new(vec![from, to])),
+        _ => None,
+    };
+    let mut has_area = false;
+    let mut has_flags = false;
+    for &(object, x, y) in objects.iter() {
+        match object {
+            Object::Area => {
+                prefab.add(None, Some(area(x, y, path.clone(), teams)));
+                has_area = true;
+            }
+            Object::Target(team) => {
+                prefab.add(None, Some(area_target(x, y, team)));
+            }
+            Object::Flag(team) => {
+                prefab.add(None, Some(flag(x, y, team, teams)));
+                has_flags = true;
+            }
+            Object::Spawn(_) => {}
+        }
+    }
+
+    let modes = default_modes(has_area, path.is_some(), has_flags);
+    prefab.add(None, Some(map_info(modes)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAP: &str = "\
+// the area between the targets, a flag below each
+#######
+#0a@b1#
+#.A.B.#
+#######
+";
+
+    #[test]
+    fn round_trips_through_a_prefab() {
+        let grid = Grid::parse(MAP).unwrap();
+        assert_eq!((grid.width(), grid.height()), (7, 4));
+        let (back, warnings) = Grid::from_prefab(&grid.to_prefab(&Teams::default()));
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        assert_eq!(back, grid);
+    }
+
+    #[test]
+    fn merges_walls_into_rectangles() {
+        let grid = Grid::parse(MAP).unwrap();
+        let solid: Vec<Vec<bool>> = (0..grid.height())
+            .map(|y| (0..grid.width()).map(|x| grid.get(x, y) == WALL).collect())
+            .collect();
+        // the bottom row, the two sides and what is left of the top row
+        assert_eq!(merge_walls(&solid).len(), 4);
+
+        let block = vec![vec![true; 3]; 2];
+        assert_eq!(
+            merge_walls(&block),
+            vec![TileRect {
+                x: 0,
+                y: 0,
+                w: 3,
+                h: 2
+            }]
+        );
+        let corner = vec![vec![true, true], vec![true, false]];
+        assert_eq!(merge_walls(&corner).len(), 2);
+    }
+
+    #[test]
+    fn reports_where_an_unknown_tile_is() {
+        let error = Grid::parse("// comments count as lines\n###\n#?#\n###").unwrap_err();
+        assert_eq!((error.line, error.col), (3, 2));
+        assert_eq!(error.message, "unknown tile '?'");
+    }
+
+    #[test]
+    fn pads_short_rows_with_floor() {
+        let grid = Grid::parse("###\n#\n###").unwrap();
+        assert_eq!(grid.rows[1], vec![WALL, FLOOR, FLOOR]);
+        assert!(Grid::parse("\n// nothing\n\n").is_err());
+    }
+}
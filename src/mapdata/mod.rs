@@ -0,0 +1,220 @@
+// building and checking map prefabs, shared by the game and the maptool binary
+
+use amethyst::{
+    assets::Prefab,
+    core::{math::Vector3, Transform},
+};
+use ron::ser::PrettyConfig;
+
+use crate::common::collision2d::RectCollider;
+use crate::components::*;
+use crate::prefab::{MapPrefabData, SpriteRenderPrefab};
+use crate::resources::{GameMode, Teams};
+
+mod edit;
+mod generate;
+mod grid;
+mod tiled;
+mod validate;
+
+pub use self::edit::{snap, MapDocument, Piece, SNAP};
+pub use self::generate::generate;
+pub use self::grid::{Grid, GridError, Object};
+pub use self::tiled::{import_tiled, is_tiled, TiledError};
+
+pub const TILE: f32 = 32.0;
+
+// a map path that is generated instead of loaded, the seed follows it as "random:<seed>"
+pub const RANDOM_MAP: &str = "random";
+
+pub const WALL_SPRITE: usize = 0;
+const PAD_SPRITE: usize = 8;
+const AREA_TARGET_SPRITE: usize = 19;
+const OBJECTIVE_SCALE: f32 = 3.0;
+const PAD_SCALE: f32 = 4.0;
+const AREA_SIZE: f32 = 80.0;
+const FLAG_SIZE: f32 = 24.0;
+
+pub fn random_map_path(seed: u64) -> String {
+    format!("{}:{}", RANDOM_MAP, seed)
+}
+
+pub fn random_map_seed(path: &str) -> Option<u64> {
+    let mut parts = path.splitn(2, ':');
+    match (parts.next(), parts.next()) {
+        (Some(RANDOM_MAP), Some(seed)) => seed.parse().ok(),
+        _ => None,
+    }
+}
+
+// a fresh seed for "random", so the map can be played again from its path
+pub fn resolve_map(path: &str) -> String {
+    if path == RANDOM_MAP {
+        random_map_path(rand::random::<u32>() as u64)
+    } else {
+        path.to_string()
+    }
+}
+
+// a rectangle of tiles, counted from the bottom left
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TileRect {
+    pub x: usize,
+    pub y: usize,
+    pub w: usize,
+    pub h: usize,
+}
+
+// covers the solid tiles with as few rectangles as it greedily can,
+// solid[y][x] with y counted from the bottom
+pub fn merge_walls(solid: &[Vec<bool>]) -> Vec<TileRect> {
+    let mut used: Vec<Vec<bool>> = solid.iter().map(|row| vec![false; row.len()]).collect();
+    let free = |used: &Vec<Vec<bool>>, x: usize, y: usize| {
+        solid[y].get(x).copied().unwrap_or(false) && !used[y][x]
+    };
+    let mut rects = Vec::new();
+    for y in 0..solid.len() {
+        for x in 0..solid[y].len() {
+            if !free(&used, x, y) {
+                continue;
+            }
+            let mut w = 1;
+            while free(&used, x + w, y) {
+                w += 1;
+            }
+            let mut h = 1;
+            while y + h < solid.len() && (x..x + w).all(|x| free(&used, x, y + h)) {
+                h += 1;
+            }
+            for row in used.iter_mut().skip(y).take(h) {
+                for cell in row.iter_mut().skip(x).take(w) {
+                    *cell = true;
+                }
+            }
+            rects.push(TileRect { x, y, w, h });
+        }
+    }
+    rects
+}
+
+// the tiles a wall collider covers, and whether it had to be snapped to them
+pub fn wall_tiles(x: f32, y: f32, collider: &RectCollider) -> (Option<TileRect>, bool) {
+    let tile = |v: f32| (v / TILE).round().max(0.0) as usize;
+    let (left, bottom) = (x - collider.width / 2.0, y - collider.height / 2.0);
+    let (x0, x1) = (tile(left), tile(x + collider.width / 2.0));
+    let (y0, y1) = (tile(bottom), tile(y + collider.height / 2.0));
+    let snapped = x0 as f32 * TILE != left || y0 as f32 * TILE != bottom;
+    let rect = if x1 > x0 && y1 > y0 {
+        Some(TileRect {
+            x: x0,
+            y: y0,
+            w: x1 - x0,
+            h: y1 - y0,
+        })
+    } else {
+        None
+    };
+    (rect, snapped)
+}
+
+fn transform(x: f32, y: f32, scale_x: f32, scale_y: f32) -> Transform {
+    let mut transform = Transform::default();
+    transform.set_translation_xyz(x, y, 0.0);
+    transform.set_scale(Vector3::new(scale_x, scale_y, 1.0));
+    transform
+}
+
+fn sprite(sprite_number: usize) -> Option<SpriteRenderPrefab> {
+    Some(SpriteRenderPrefab { sprite_number })
+}
+
+// the root the game tags as the map comes with the prefab
+pub fn map_prefab() -> Prefab<MapPrefabData> {
+    Prefab::new()
+}
+
+// the sprite is one tile, stretched over the rectangle
+pub fn wall(rect: TileRect, sprite_number: usize) -> MapPrefabData {
+    let (w, h) = (rect.w as f32, rect.h as f32);
+    let x = rect.x as f32 * TILE + w * TILE / 2.0;
+    let y = rect.y as f32 * TILE + h * TILE / 2.0;
+    MapPrefabData {
+        transform: Some(transform(x, y, w, h)),
+        collider: Some(RectCollider::new("Wall", w * TILE, h * TILE)),
+        sprite: sprite(sprite_number),
+        ..Default::default()
+    }
+}
+
+// only the pad is drawn, the other spawn points of a team share it
+pub fn spawn_point(x: f32, y: f32, team: u32, pad: Option<&Teams>) -> MapPrefabData {
+    MapPrefabData {
+        transform: Some(match pad {
+            Some(_) => transform(x, y, PAD_SCALE, PAD_SCALE),
+            None => transform(x, y, 1.0, 1.0),
+        }),
+        sprite: pad.and_then(|teams| sprite(PAD_SPRITE + teams.sprite_offset(team))),
+        spawn_point: Some(SpawnPoint { team }),
+        ..Default::default()
+    }
+}
+
+pub fn area(x: f32, y: f32, path: Option<AreaPath>, teams: &Teams) -> MapPrefabData {
+    MapPrefabData {
+        transform: Some(transform(x, y, OBJECTIVE_SCALE, OBJECTIVE_SCALE)),
+        collider: Some(RectCollider::new("Area", AREA_SIZE, AREA_SIZE)),
+        sprite: sprite(teams.neutral_area_sprite),
+        area: Some(Area),
+        area_path: path,
+        ..Default::default()
+    }
+}
+
+pub fn area_target(x: f32, y: f32, team: u32) -> MapPrefabData {
+    MapPrefabData {
+        transform: Some(transform(x, y, OBJECTIVE_SCALE, OBJECTIVE_SCALE)),
+        sprite: sprite(AREA_TARGET_SPRITE),
+        area_target: Some(AreaTarget { team }),
+        ..Default::default()
+    }
+}
+
+pub fn flag(x: f32, y: f32, team: u32, teams: &Teams) -> MapPrefabData {
+    MapPrefabData {
+        transform: Some(transform(x, y, 1.0, 1.0)),
+        collider: Some(RectCollider::new("Flag", FLAG_SIZE, FLAG_SIZE)),
+        sprite: teams
+            .get(team as usize)
+            .and_then(|team| sprite(team.area_sprite)),
+        flag: Some(Flag::new(team)),
+        ..Default::default()
+    }
+}
+
+// the modes a map with these objectives can be played in
+pub fn default_modes(area: bool, path: bool, flags: bool) -> Vec<GameMode> {
+    let mut modes = Vec::new();
+    if area && path {
+        modes.push(GameMode::Area);
+    }
+    modes.push(GameMode::TeamDeathmatch);
+    if area {
+        modes.push(GameMode::KingOfTheHill);
+    }
+    if flags {
+        modes.push(GameMode::CaptureTheFlag);
+    }
+    modes
+}
+
+pub fn map_info(modes: Vec<GameMode>) -> MapPrefabData {
+    MapPrefabData {
+        map_info: Some(MapInfo { modes }),
+        ..Default::default()
+    }
+}
+
+pub fn to_ron(prefab: &Prefab<MapPrefabData>) -> Result<String, ron::Error> {
+    // one entity per line, like the hand written maps
+    ron::ser::to_string_pretty(prefab, PrettyConfig::new().with_depth_limit(2))
+}
@@ -0,0 +1,696 @@
+use amethyst::assets::Prefab;
+use serde_derive::Deserialize;
+use serde_json::Value;
+use std::{collections::HashMap, fmt, str::FromStr};
+
+use super::{
+    area, area_target, default_modes, flag, map_info, map_prefab, merge_walls, spawn_point, wall,
+    TILE,
+};
+use crate::components::AreaPath;
+use crate::prefab::{MapPrefabData, SpriteRenderPrefab};
+use crate::resources::{GameMode, Teams};
+
+// maps made in Tiled, saved as .tmx or .json with the CSV tile layer format.
+// every tile layer is walls, the tile ids being sprite numbers of texture/spritesheet.ron.
+// objects are told apart by their type (class in some versions) or name:
+// SpawnPoint, AreaTarget and Flag with an int property "team", Area, and an AreaPath
+// polyline with the optional properties "spline" and "checkpoints" ("0.2, 0.8").
+// objects placed as tiles keep the tile as their sprite.
+// the map property "modes" ("Area, TeamDeathmatch") lists the modes of the map,
+// otherwise they follow from its objectives
+
+const CSV_ONLY: &str =
+    "only the CSV tile layer format is supported, change it in the map properties";
+
+// the high bits of a tile id are its flips
+const GID_MASK: u32 = 0x1fff_ffff;
+
+#[derive(Debug)]
+pub enum TiledError {
+    Xml(roxmltree::Error),
+    Json(serde_json::Error),
+    Invalid(String),
+}
+
+impl fmt::Display for TiledError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TiledError::Xml(e) => e.fmt(f),
+            TiledError::Json(e) => e.fmt(f),
+            TiledError::Invalid(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for TiledError {}
+
+impl From<roxmltree::Error> for TiledError {
+    fn from(e: roxmltree::Error) -> Self {
+        TiledError::Xml(e)
+    }
+}
+
+impl From<serde_json::Error> for TiledError {
+    fn from(e: serde_json::Error) -> Self {
+        TiledError::Json(e)
+    }
+}
+
+fn invalid(message: impl Into<String>) -> TiledError {
+    TiledError::Invalid(message.into())
+}
+
+pub fn is_tiled(path: &str) -> bool {
+    path.ends_with(".tmx") || path.ends_with(".tmj") || path.ends_with(".json")
+}
+
+// the prefab and what was left out of it
+pub fn import_tiled(
+    text: &str,
+    path: &str,
+    teams: &Teams,
+) -> Result<(Prefab<MapPrefabData>, Vec<String>), TiledError> {
+    let map = if path.ends_with(".tmx") {
+        parse_tmx(text)?
+    } else {
+        parse_json(text)?
+    };
+    map.to_prefab(teams)
+}
+
+type Properties = HashMap<String, String>;
+
+struct TiledMap {
+    width: usize,
+    height: usize,
+    tile_width: f32,
+    tile_height: f32,
+    // the first tile id of each tileset
+    first_gids: Vec<u32>,
+    properties: Properties,
+    // tile ids row by row from the top, 0 is no tile
+    layers: Vec<Vec<u32>>,
+    objects: Vec<TiledObject>,
+}
+
+struct TiledObject {
+    id: u32,
+    kind: String,
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+    gid: Option<u32>,
+    // relative to x and y
+    polyline: Vec<(f32, f32)>,
+    properties: Properties,
+}
+
+impl TiledMap {
+    fn sprite(&self, gid: u32) -> Option<usize> {
+        let gid = gid & GID_MASK;
+        if gid == 0 {
+            return None;
+        }
+        let first = self
+            .first_gids
+            .iter()
+            .copied()
+            .filter(|&first| first <= gid)
+            .max()
+            .unwrap_or(1);
+        Some((gid - first) as usize)
+    }
+
+    fn to_prefab(&self, teams: &Teams) -> Result<(Prefab<MapPrefabData>, Vec<String>), TiledError> {
+        let mut warnings = Vec::new();
+        let mut prefab = map_prefab();
+
+        for (i, layer) in self.layers.iter().enumerate() {
+            if layer.len() != self.width * self.height {
+                return Err(invalid(format!(
+                    "tile layer {}: {} tiles for a {}x{} map",
+                    i,
+                    layer.len(),
+                    self.width,
+                    self.height
+                )));
+            }
+            // tiles are merged with the same tiles only, the sprite is stretched over them
+            let mut sprites: Vec<usize> =
+                layer.iter().filter_map(|&gid| self.sprite(gid)).collect();
+            sprites.sort_unstable();
+            sprites.dedup();
+            for sprite in sprites {
+                let solid: Vec<Vec<bool>> = layer
+                    .chunks(self.width)
+                    .rev()
+                    .map(|row| {
+                        row.iter()
+                            .map(|&gid| self.sprite(gid) == Some(sprite))
+                            .collect()
+                    })
+                    .collect();
+                for rect in merge_walls(&solid) {
+                    prefab.add(None, Some(wall(rect, sprite)));
+                }
+            }
+        }
+
+        // the game's tiles are TILE wide whatever size the map was drawn with, and y goes up
+        let height = self.height as f32 * TILE;
+        let position = |x: f32, y: f32| {
+            (
+                x * TILE / self.tile_width,
+                height - y * TILE / self.tile_height,
+            )
+        };
+
+        let mut entities = Vec::new();
+        let mut path = None;
+        let mut targets = Vec::new();
+        for object in self.objects.iter() {
+            let name = format!("object {} ({})", object.id, object.kind);
+            // tile objects hang from their bottom left corner, the others from the top left
+            let (x, y) = match object.gid {
+                Some(_) => position(
+                    object.x + object.width / 2.0,
+                    object.y - object.height / 2.0,
+                ),
+                None => position(
+                    object.x + object.width / 2.0,
+                    object.y + object.height / 2.0,
+                ),
+            };
+            let team = || {
+                object
+                    .properties
+                    .get("team")
+                    .and_then(|team| team.parse::<u32>().ok())
+                    .ok_or_else(|| invalid(format!("{}: needs an int property \"team\"", name)))
+            };
+
+            let mut data = match object.kind.to_lowercase().replace('_', "").as_str() {
+                "spawnpoint" => spawn_point(x, y, team()?, None),
+                "area" => area(x, y, None, teams),
+                "areatarget" => {
+                    let team = team()?;
+                    targets.push((team, (x, y)));
+                    area_target(x, y, team)
+                }
+                "flag" => flag(x, y, team()?, teams),
+                "areapath" => {
+                    let points = object
+                        .polyline
+                        .iter()
+                        .map(|&(px, py)| position(object.x + px, object.y + py))
+                        .collect();
+                    let mut area_path = AreaPath::new(points);
+                    area_path.spline = object
+                        .properties
+                        .get("spline")
+                        .map_or(false, |spline| spline == "true");
+                    if let Some(checkpoints) = object.properties.get("checkpoints") {
+                        area_path.checkpoints = parse_list(checkpoints).map_err(|item| {
+                            invalid(format!("{}: checkpoint \"{}\" is not a number", name, item))
+                        })?;
+                    }
+                    if area_path.points.len() < 2 {
+                        warnings.push(format!("{}: a path needs at least two points", name));
+                    }
+                    path = Some(area_path);
+                    continue;
+                }
+                _ => {
+                    warnings.push(format!("{}: unknown object, ignored", name));
+                    continue;
+                }
+            };
+            if let Some(sprite_number) = object.gid.and_then(|gid| self.sprite(gid)) {
+                data.sprite = Some(SpriteRenderPrefab { sprite_number });
+            }
+            entities.push(data);
+        }
+
+        // without a drawn path the area goes straight from the first team's target to the second's
+        if path.is_none() {
+            let target = |team: u32| targets.iter().find(|t| t.0 == team).map(|t| t.1);
+            if let (Some(from), Some(to)) = (target(0), target(1)) {
+                path = Some(AreaPath::new(vec![from, to]));
+            }
+        }
+        let mut has_area = false;
+        let mut has_flags = false;
+        for data in entities.iter_mut() {
+            if data.area.is_some() {
+                data.area_path = path.clone();
+                has_area = true;
+            }
+            has_flags |= data.flag.is_some();
+        }
+
+        let modes = match self.properties.get("modes") {
+            Some(modes) => parse_list::<GameMode>(modes)
+                .map_err(|mode| invalid(format!("modes: unknown mode \"{}\"", mode)))?,
+            None => default_modes(has_area, path.is_some(), has_flags),
+        };
+        if modes.is_empty() {
+            return Err(invalid("modes: at least one mode is required"));
+        }
+
+        for data in entities {
+            prefab.add(None, Some(data));
+        }
+        prefab.add(None, Some(map_info(modes)));
+        Ok((prefab, warnings))
+    }
+}
+
+// a comma separated list of RON values, the item that failed on error
+fn parse_list<T: serde::de::DeserializeOwned>(list: &str) -> Result<Vec<T>, String> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(|item| ron::de::from_str(item).map_err(|_| item.to_string()))
+        .collect()
+}
+
+#[derive(Deserialize)]
+struct JsonMap {
+    width: usize,
+    height: usize,
+    tilewidth: f32,
+    tileheight: f32,
+    #[serde(default)]
+    infinite: bool,
+    #[serde(default)]
+    tilesets: Vec<JsonTileset>,
+    #[serde(default)]
+    layers: Vec<JsonLayer>,
+    #[serde(default)]
+    properties: Vec<JsonProperty>,
+}
+
+#[derive(Deserialize)]
+struct JsonTileset {
+    firstgid: u32,
+}
+
+#[derive(Deserialize)]
+struct JsonLayer {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    data: Option<Value>,
+    #[serde(default)]
+    encoding: Option<String>,
+    #[serde(default)]
+    objects: Vec<JsonObject>,
+    // of a group
+    #[serde(default)]
+    layers: Vec<JsonLayer>,
+}
+
+#[derive(Deserialize)]
+struct JsonObject {
+    #[serde(default)]
+    id: u32,
+    #[serde(default)]
+    name: String,
+    #[serde(default, rename = "type")]
+    kind: String,
+    #[serde(default)]
+    class: String,
+    x: f32,
+    y: f32,
+    #[serde(default)]
+    width: f32,
+    #[serde(default)]
+    height: f32,
+    #[serde(default)]
+    gid: Option<u32>,
+    #[serde(default)]
+    polyline: Vec<JsonPoint>,
+    #[serde(default)]
+    properties: Vec<JsonProperty>,
+}
+
+#[derive(Deserialize)]
+struct JsonPoint {
+    x: f32,
+    y: f32,
+}
+
+#[derive(Deserialize)]
+struct JsonProperty {
+    name: String,
+    value: Value,
+}
+
+fn json_properties(properties: Vec<JsonProperty>) -> Properties {
+    properties
+        .into_iter()
+        .map(|property| {
+            let value = match property.value {
+                Value::String(value) => value,
+                value => value.to_string(),
+            };
+            (property.name, value)
+        })
+        .collect()
+}
+
+fn parse_json(text: &str) -> Result<TiledMap, TiledError> {
+    let json: JsonMap = serde_json::from_str(text)?;
+    if json.infinite {
+        return Err(invalid("infinite maps are not supported"));
+    }
+    let mut map = TiledMap {
+        width: json.width,
+        height: json.height,
+        tile_width: json.tilewidth,
+        tile_height: json.tileheight,
+        first_gids: json
+            .tilesets
+            .iter()
+            .map(|tileset| tileset.firstgid)
+            .collect(),
+        properties: json_properties(json.properties),
+        layers: Vec::new(),
+        objects: Vec::new(),
+    };
+    add_json_layers(&mut map, json.layers)?;
+    Ok(map)
+}
+
+fn add_json_layers(map: &mut TiledMap, layers: Vec<JsonLayer>) -> Result<(), TiledError> {
+    for layer in layers {
+        match layer.kind.as_str() {
+            "tilelayer" => {
+                if layer.encoding.as_deref().map_or(false, |e| e != "csv") {
+                    return Err(invalid(CSV_ONLY));
+                }
+                let data = layer.data.unwrap_or(Value::Null);
+                map.layers.push(serde_json::from_value(data)?);
+            }
+            "objectgroup" => {
+                for object in layer.objects {
+                    let kind = if !object.kind.is_empty() {
+                        object.kind
+                    } else if !object.class.is_empty() {
+                        object.class
+                    } else {
+                        object.name
+                    };
+                    map.objects.push(TiledObject {
+                        id: object.id,
+                        kind,
+                        x: object.x,
+                        y: object.y,
+                        width: object.width,
+                        height: object.height,
+                        gid: object.gid,
+                        polyline: object.polyline.iter().map(|p| (p.x, p.y)).collect(),
+                        properties: json_properties(object.properties),
+                    });
+                }
+            }
+            "group" => add_json_layers(map, layer.layers)?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn attr<T: FromStr>(node: roxmltree::Node<'_, '_>, name: &str) -> Result<T, TiledError> {
+    let element = node.tag_name().name();
+    let value = node
+        .attribute(name)
+        .ok_or_else(|| invalid(format!("<{}> has no {} attribute", element, name)))?;
+    value.parse().map_err(|_| {
+        invalid(format!(
+            "<{}> {}: \"{}\" is not valid",
+            element, name, value
+        ))
+    })
+}
+
+fn attr_or<T: FromStr>(
+    node: roxmltree::Node<'_, '_>,
+    name: &str,
+    default: T,
+) -> Result<T, TiledError> {
+    match node.attribute(name) {
+        Some(_) => attr(node, name),
+        None => Ok(default),
+    }
+}
+
+fn tmx_properties(node: roxmltree::Node<'_, '_>) -> Properties {
+    node.children()
+        .filter(|child| child.has_tag_name("properties"))
+        .flat_map(|properties| properties.children())
+        .filter(|child| child.has_tag_name("property"))
+        .filter_map(|property| {
+            // long strings are kept as the text of the element
+            let value = property.attribute("value").or_else(|| property.text());
+            Some((
+                property.attribute("name")?.to_string(),
+                value.unwrap_or("").to_string(),
+            ))
+        })
+        .collect()
+}
+
+fn parse_tmx(text: &str) -> Result<TiledMap, TiledError> {
+    let document = roxmltree::Document::parse(text)?;
+    let root = document.root_element();
+    if !root.has_tag_name("map") {
+        return Err(invalid("not a Tiled map, the root element is not <map>"));
+    }
+    if root.attribute("infinite") == Some("1") {
+        return Err(invalid("infinite maps are not supported"));
+    }
+    let mut map = TiledMap {
+        width: attr(root, "width")?,
+        height: attr(root, "height")?,
+        tile_width: attr(root, "tilewidth")?,
+        tile_height: attr(root, "tileheight")?,
+        first_gids: Vec::new(),
+        properties: tmx_properties(root),
+        layers: Vec::new(),
+        objects: Vec::new(),
+    };
+    for tileset in root
+        .children()
+        .filter(|child| child.has_tag_name("tileset"))
+    {
+        map.first_gids.push(attr(tileset, "firstgid")?);
+    }
+    add_tmx_layers(&mut map, root)?;
+    Ok(map)
+}
+
+fn add_tmx_layers(map: &mut TiledMap, node: roxmltree::Node<'_, '_>) -> Result<(), TiledError> {
+    for child in node.children().filter(|child| child.is_element()) {
+        match child.tag_name().name() {
+            "layer" => {
+                let data = child
+                    .children()
+                    .find(|data| data.has_tag_name("data"))
+                    .ok_or_else(|| invalid("<layer> has no <data>"))?;
+                let tiles = match data.attribute("encoding") {
+                    Some("csv") => data
+                        .text()
+                        .unwrap_or("")
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|gid| !gid.is_empty())
+                        .map(|gid| {
+                            gid.parse()
+                                .map_err(|_| invalid(format!("\"{}\" is not a tile id", gid)))
+                        })
+                        .collect::<Result<Vec<u32>, _>>()?,
+                    None => data
+                        .children()
+                        .filter(|tile| tile.has_tag_name("tile"))
+                        .map(|tile| attr_or(tile, "gid", 0))
+                        .collect::<Result<Vec<u32>, _>>()?,
+                    Some(_) => return Err(invalid(CSV_ONLY)),
+                };
+                map.layers.push(tiles);
+            }
+            "objectgroup" => {
+                for object in child
+                    .children()
+                    .filter(|object| object.has_tag_name("object"))
+                {
+                    let kind = object
+                        .attribute("type")
+                        .or_else(|| object.attribute("class"))
+                        .or_else(|| object.attribute("name"))
+                        .unwrap_or("");
+                    let polyline = match object.children().find(|p| p.has_tag_name("polyline")) {
+                        Some(polyline) => parse_points(polyline.attribute("points").unwrap_or(""))?,
+                        None => Vec::new(),
+                    };
+                    map.objects.push(TiledObject {
+                        id: attr_or(object, "id", 0)?,
+                        kind: kind.to_string(),
+                        x: attr(object, "x")?,
+                        y: attr(object, "y")?,
+                        width: attr_or(object, "width", 0.0)?,
+                        height: attr_or(object, "height", 0.0)?,
+                        gid: match object.attribute("gid") {
+                            Some(_) => Some(attr(object, "gid")?),
+                            None => None,
+                        },
+                        polyline,
+                        properties: tmx_properties(object),
+                    });
+                }
+            }
+            "group" => add_tmx_layers(map, child)?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+// "x,y x,y ..."
+fn parse_points(points: &str) -> Result<Vec<(f32, f32)>, TiledError> {
+    points
+        .split_whitespace()
+        .map(|point| {
+            let mut xy = point.split(',').map(|v| v.parse::<f32>());
+            match (xy.next(), xy.next()) {
+                (Some(Ok(x)), Some(Ok(y))) => Ok((x, y)),
+                _ => Err(invalid(format!("\"{}\" is not a point", point))),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entities(prefab: &Prefab<MapPrefabData>) -> Vec<&MapPrefabData> {
+        prefab
+            .entities()
+            .filter_map(|entity| entity.data())
+            .collect()
+    }
+
+    fn position(data: &MapPrefabData) -> (f32, f32) {
+        let translation = data.transform.as_ref().unwrap().translation();
+        (translation.x, translation.y)
+    }
+
+    fn walls<'a>(entities: &[&'a MapPrefabData]) -> Vec<&'a MapPrefabData> {
+        entities
+            .iter()
+            .copied()
+            .filter(|data| data.collider.as_ref().map_or(false, |c| c.tag == "Wall"))
+            .collect()
+    }
+
+    // drawn with 16px tiles, the second wall tile is flipped
+    const TMX: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.5" orientation="orthogonal" width="3" height="2" tilewidth="16" tileheight="16">
+ <tileset firstgid="1" name="walls" tilewidth="16" tileheight="16"/>
+ <layer id="1" name="walls" width="3" height="2">
+  <data encoding="csv">
+1,0,0,
+2147483649,0,0
+</data>
+ </layer>
+ <objectgroup id="2" name="objects">
+  <object id="1" type="SpawnPoint" x="24" y="8">
+   <properties>
+    <property name="team" type="int" value="0"/>
+   </properties>
+  </object>
+  <object id="2" type="Tree" x="40" y="8"/>
+ </objectgroup>
+</map>
+"#;
+
+    #[test]
+    fn imports_tmx() {
+        let (prefab, warnings) = import_tiled(TMX, "test.tmx", &Teams::default()).unwrap();
+        assert_eq!(warnings, vec!["object 2 (Tree): unknown object, ignored"]);
+
+        let entities = entities(&prefab);
+        // the flipped tile is the same wall sprite, so both tiles are one wall
+        let walls = walls(&entities);
+        assert_eq!(walls.len(), 1);
+        assert_eq!(position(walls[0]), (16.0, 32.0));
+        assert_eq!(walls[0].sprite.as_ref().unwrap().sprite_number, 0);
+        let collider = walls[0].collider.as_ref().unwrap();
+        assert_eq!((collider.width, collider.height), (32.0, 64.0));
+
+        // scaled to the game's tiles and measured from the bottom
+        let spawn = entities
+            .iter()
+            .find(|data| data.spawn_point.is_some())
+            .unwrap();
+        assert_eq!(position(spawn), (48.0, 48.0));
+        assert_eq!(spawn.spawn_point.as_ref().unwrap().team, 0);
+    }
+
+    const JSON: &str = r#"{
+        "width": 2, "height": 1, "tilewidth": 32, "tileheight": 32,
+        "tilesets": [{"firstgid": 1}],
+        "layers": [
+            {"type": "tilelayer", "data": [0, 1073741826]},
+            {"type": "objectgroup", "objects": [
+                {"id": 1, "type": "Area", "x": 16, "y": 16},
+                {"id": 2, "class": "Flag", "x": 48, "y": 16,
+                 "properties": [{"name": "team", "type": "int", "value": 1}]},
+                {"id": 3, "name": "SpawnPoint", "gid": 3, "x": 0, "y": 32,
+                 "width": 32, "height": 32,
+                 "properties": [{"name": "team", "type": "int", "value": 1}]},
+                {"id": 4, "type": "Lamp", "x": 0, "y": 0}
+            ]}
+        ]
+    }"#;
+
+    #[test]
+    fn imports_json() {
+        let (prefab, warnings) = import_tiled(JSON, "test.json", &Teams::default()).unwrap();
+        assert_eq!(warnings, vec!["object 4 (Lamp): unknown object, ignored"]);
+
+        let entities = entities(&prefab);
+        let walls = walls(&entities);
+        assert_eq!(walls.len(), 1);
+        assert_eq!(position(walls[0]), (48.0, 16.0));
+        assert_eq!(walls[0].sprite.as_ref().unwrap().sprite_number, 1);
+
+        let area = entities.iter().find(|data| data.area.is_some()).unwrap();
+        assert_eq!(position(area), (16.0, 16.0));
+        let flag = entities.iter().find(|data| data.flag.is_some()).unwrap();
+        assert_eq!(position(flag), (48.0, 16.0));
+
+        // a tile object hangs from its bottom left corner and keeps its tile
+        let spawn = entities
+            .iter()
+            .find(|data| data.spawn_point.is_some())
+            .unwrap();
+        assert_eq!(position(spawn), (16.0, 16.0));
+        assert_eq!(spawn.spawn_point.as_ref().unwrap().team, 1);
+        assert_eq!(spawn.sprite.as_ref().unwrap().sprite_number, 2);
+    }
+
+    #[test]
+    fn rejects_what_it_cannot_place() {
+        let no_team = TMX.replace(r#"name="team" type="int" value="0""#, r#"name="side""#);
+        let error = import_tiled(&no_team, "test.tmx", &Teams::default()).unwrap_err();
+        assert!(error.to_string().contains("needs an int property \"team\""));
+
+        let base64 = TMX.replace(r#"encoding="csv""#, r#"encoding="base64""#);
+        let error = import_tiled(&base64, "test.tmx", &Teams::default()).unwrap_err();
+        assert_eq!(error.to_string(), CSV_ONLY);
+    }
+}
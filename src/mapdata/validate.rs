@@ -0,0 +1,466 @@
+use amethyst::{assets::Prefab, core::math::Vector2};
+
+use crate::common::collision2d::{FIELD_HEIGHT, FIELD_WIDTH};
+use crate::components::{AreaPath, GadgetKind};
+use crate::prefab::MapPrefabData;
+use crate::resources::{GameMode, NavGrid, NavRect};
+use crate::validation::{Validate, Validator};
+
+// steps the area path is followed in when looking for walls on it
+const PATH_STEPS: usize = 100;
+
+#[derive(Clone, Copy, Debug)]
+struct Rect {
+    left: f32,
+    bottom: f32,
+    right: f32,
+    top: f32,
+}
+
+impl Rect {
+    fn overlaps(&self, other: &Rect) -> bool {
+        self.left < other.right
+            && other.left < self.right
+            && self.bottom < other.top
+            && other.bottom < self.top
+    }
+
+    fn contains(&self, (x, y): (f32, f32)) -> bool {
+        self.left < x && x < self.right && self.bottom < y && y < self.top
+    }
+}
+
+impl Validate for Prefab<MapPrefabData> {
+    fn validate(&self, v: &mut Validator) {
+        let mut walls = Vec::new();
+        let mut spawns = Vec::new();
+        let mut areas = Vec::new();
+        let mut targets = Vec::new();
+        let mut flags = Vec::new();
+        let mut paths = Vec::new();
+        let mut teleporters = Vec::new();
+        let mut modes = None;
+
+        for (i, entity) in self.entities().enumerate() {
+            let data = match entity.data() {
+                Some(data) => data,
+                None => continue,
+            };
+            let field = |name: &str| format!("entities[{}].{}", i, name);
+            if let Some(sprite) = &data.sprite {
+                v.sprite(&field("sprite.sprite_number"), sprite.sprite_number);
+            }
+            if let Some(collider) = &data.collider {
+                v.positive(&field("collider.width"), collider.width);
+                v.positive(&field("collider.height"), collider.height);
+            }
+            if let Some(destructible) = &data.destructible {
+                v.positive(&field("destructible.hp"), destructible.hp);
+                for (k, &sprite) in destructible.damage_sprites.iter().enumerate() {
+                    v.sprite(
+                        &field(&format!("destructible.damage_sprites[{}]", k)),
+                        sprite,
+                    );
+                }
+                v.warn(
+                    data.collider.as_ref().map_or(false, |c| c.tag == "Wall"),
+                    format!("{}: only walls are broken", field("destructible")),
+                );
+            }
+            if let Some(gadget) = &data.gadget {
+                v.check(
+                    data.collider.as_ref().map_or(false, |c| c.tag == "Gadget"),
+                    format!(
+                        "{}: needs a \"Gadget\" collider to be touched",
+                        field("gadget")
+                    ),
+                );
+                let kind = field("gadget.kind");
+                match gadget.kind {
+                    GadgetKind::DamageZone(damage) => v.non_negative(&kind, damage),
+                    GadgetKind::SlowZone(value) => v.check(
+                        value >= 0.0 && value <= 1.0,
+                        format!("{}: must be between 0 and 1 (got {})", kind, value),
+                    ),
+                    GadgetKind::Teleporter(channel) => teleporters.push((i, channel)),
+                    _ => {}
+                }
+            }
+            if let Some(path) = &data.area_path {
+                v.warn(
+                    data.area.is_some(),
+                    format!("{}: only used on the area", field("area_path")),
+                );
+                paths.push((i, path));
+            }
+            if let Some(info) = &data.map_info {
+                modes = Some((i, &info.modes));
+            }
+
+            let placed = data.collider.is_some()
+                || data.spawn_point.is_some()
+                || data.area.is_some()
+                || data.area_target.is_some()
+                || data.flag.is_some();
+            let (x, y) = match &data.transform {
+                Some(transform) => (transform.translation().x, transform.translation().y),
+                None => {
+                    v.check(!placed, format!("{}: is required", field("transform")));
+                    continue;
+                }
+            };
+
+            if let Some(collider) = data.collider.as_ref().filter(|c| c.tag == "Wall") {
+                let rect = Rect {
+                    left: x - collider.width / 2.0,
+                    bottom: y - collider.height / 2.0,
+                    right: x + collider.width / 2.0,
+                    top: y + collider.height / 2.0,
+                };
+                v.warn(
+                    rect.left >= 0.0
+                        && rect.bottom >= 0.0
+                        && rect.right <= FIELD_WIDTH
+                        && rect.top <= FIELD_HEIGHT,
+                    format!(
+                        "entities[{}]: the wall spans ({}, {}) to ({}, {}), \
+                         outside the {}x{} the collision grid covers",
+                        i, rect.left, rect.bottom, rect.right, rect.top, FIELD_WIDTH, FIELD_HEIGHT
+                    ),
+                );
+                walls.push((i, rect));
+            }
+            if let Some(spawn_point) = &data.spawn_point {
+                v.team(&field("spawn_point.team"), spawn_point.team);
+                spawns.push((i, spawn_point.team, (x, y)));
+            }
+            if data.area.is_some() {
+                areas.push((i, (x, y)));
+            }
+            if let Some(target) = &data.area_target {
+                v.team(&field("area_target.team"), target.team);
+                targets.push((target.team, (x, y)));
+            }
+            if let Some(flag) = &data.flag {
+                v.team(&field("flag.team"), flag.team);
+                flags.push(flag.team);
+            }
+        }
+
+        // a team without spawn points never gets its bots, unless teams.ron places them
+        let team_count = v.context().team_sprite_offsets.len();
+        let fallbacks = v.context().team_fallback_spawns.clone();
+        for team in 0..team_count as u32 {
+            let fallback = fallbacks.get(team as usize).copied().unwrap_or(false);
+            v.check(
+                fallback || spawns.iter().any(|&(_, t, _)| t == team),
+                format!(
+                    "team {} has no spawn point, its bots would never spawn",
+                    team
+                ),
+            );
+        }
+
+        // the camera looks at the area while the game is paused
+        v.check(
+            !areas.is_empty(),
+            "no entity has an area, the camera's pause view needs one",
+        );
+        for &(i, _) in areas.iter().skip(1) {
+            v.warn(
+                false,
+                format!(
+                    "entities[{}]: the camera only looks at the first area, entities[{}]",
+                    i, areas[0].0
+                ),
+            );
+        }
+
+        if let Some((i, modes)) = modes {
+            let field = format!("entities[{}].map_info.modes", i);
+            v.check(
+                !modes.is_empty(),
+                format!("{}: at least one mode is required", field),
+            );
+            if modes.contains(&GameMode::CaptureTheFlag) {
+                for team in 0..team_count as u32 {
+                    v.check(
+                        flags.contains(&team),
+                        format!("{}: CaptureTheFlag needs a flag for team {}", field, team),
+                    );
+                }
+            }
+        }
+
+        for (a, &(i, rect)) in walls.iter().enumerate() {
+            for &(j, other) in walls[a + 1..].iter() {
+                v.warn(
+                    !rect.overlaps(&other),
+                    format!("entities[{}] and entities[{}]: the walls overlap", i, j),
+                );
+            }
+        }
+
+        // teleporters come in pairs
+        for &(i, channel) in teleporters.iter() {
+            let count = teleporters.iter().filter(|t| t.1 == channel).count();
+            v.check(
+                count == 2,
+                format!(
+                    "entities[{}]: teleporter channel {} has {} teleporters, it needs two",
+                    i, channel, count
+                ),
+            );
+        }
+
+        check_reachable(v, &walls, &spawns, &areas);
+
+        for (i, path) in paths {
+            check_path(v, i, path, &walls, &targets);
+        }
+    }
+}
+
+// every spawn point has to be walkable and lead to the area, or to the first spawn point
+fn check_reachable(
+    v: &mut Validator,
+    walls: &[(usize, Rect)],
+    spawns: &[(usize, u32, (f32, f32))],
+    areas: &[(usize, (f32, f32))],
+) {
+    let (start, goal) = match (areas.first(), spawns.first()) {
+        (Some(&(i, position)), _) => (position, format!("the area, entities[{}]", i)),
+        (None, Some(&(i, _, position))) => (position, format!("entities[{}]", i)),
+        _ => return,
+    };
+
+    // the grid the AI walks on, reaching around the outside of open maps as well
+    let rects: Vec<NavRect> = walls
+        .iter()
+        .map(|(_, rect)| {
+            let size = Vector2::new(rect.right - rect.left, rect.top - rect.bottom);
+            let center = Vector2::new(rect.left, rect.bottom) + size / 2.0;
+            (center, size)
+        })
+        .collect();
+    let point = |(x, y): (f32, f32)| Vector2::new(x, y);
+    let points: Vec<Vector2<f32>> = spawns.iter().map(|s| point(s.2)).collect();
+    let covered: Vec<Vector2<f32>> = points.iter().copied().chain(Some(point(start))).collect();
+    let grid = NavGrid::covering(&rects, &[], &covered);
+    let reaches = grid.reaches(point(start), &points);
+
+    for (&(i, _, position), reaches) in spawns.iter().zip(reaches) {
+        let inside = walls.iter().find(|(_, rect)| rect.contains(position));
+        if let Some((wall, _)) = inside {
+            v.check(
+                false,
+                format!(
+                    "entities[{}]: the spawn point is inside entities[{}]",
+                    i, wall
+                ),
+            );
+        } else {
+            v.check(
+                reaches,
+                format!("entities[{}]: the spawn point cannot reach {}", i, goal),
+            );
+        }
+    }
+}
+
+fn check_path(
+    v: &mut Validator,
+    i: usize,
+    path: &AreaPath,
+    walls: &[(usize, Rect)],
+    targets: &[(u32, (f32, f32))],
+) {
+    let field = |name: &str| format!("entities[{}].area_path.{}", i, name);
+    v.check(
+        path.points.len() >= 2,
+        format!("{}: at least two points are required", field("points")),
+    );
+    for (k, &checkpoint) in path.checkpoints.iter().enumerate() {
+        let name = field(&format!("checkpoints[{}]", k));
+        v.check(
+            checkpoint > 0.0 && checkpoint < 1.0,
+            format!("{}: must be between 0 and 1 (got {})", name, checkpoint),
+        );
+        v.warn(
+            (checkpoint - 0.5).abs() > std::f32::EPSILON,
+            format!("{}: a checkpoint at the middle never locks", name),
+        );
+    }
+    if path.points.len() < 2 {
+        return;
+    }
+
+    // the area is not stopped by walls, but players cannot follow it through them
+    for step in 0..=PATH_STEPS {
+        let position = path.position(step as f32 / PATH_STEPS as f32);
+        let position = (position.x, position.y);
+        if let Some((wall, _)) = walls.iter().find(|(_, rect)| rect.contains(position)) {
+            v.warn(
+                false,
+                format!(
+                    "{}: the path goes through entities[{}] at ({:.0}, {:.0})",
+                    field("points"),
+                    wall,
+                    position.0,
+                    position.1
+                ),
+            );
+            break;
+        }
+    }
+
+    // it starts on the first team's side
+    let target = |team: u32| targets.iter().find(|t| t.0 == team).map(|t| t.1);
+    if let (Some(first), Some(second)) = (target(0), target(1)) {
+        let start = path.points[0];
+        let distance = |(x, y): (f32, f32)| (x - start.0).powi(2) + (y - start.1).powi(2);
+        v.warn(
+            distance(first) <= distance(second),
+            format!(
+                "{}: starts on team 1's side, the first point belongs with team 0",
+                field("points")
+            ),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::ronfile;
+    use crate::mapdata::{area, wall, Grid, TileRect};
+    use crate::resources::Teams;
+    use crate::validation::DataContext;
+
+    const CLEAN: &str = "\
+#########
+#0.a@b.1#
+#########
+";
+
+    fn teams() -> Teams {
+        ronfile::from_str(include_str!("../../resources/teams.ron"), "teams.ron").unwrap()
+    }
+
+    fn prefab(grid: &str) -> Prefab<MapPrefabData> {
+        Grid::parse(grid).unwrap().to_prefab(&teams())
+    }
+
+    // the problems and the warnings
+    fn validate(prefab: &Prefab<MapPrefabData>) -> (Vec<String>, Vec<String>) {
+        let mut context = DataContext::new(64);
+        context.set_teams(&teams());
+        let mut v = Validator::new(&context);
+        prefab.validate(&mut v);
+        (v.problems().to_vec(), v.warnings().to_vec())
+    }
+
+    fn index(prefab: &Prefab<MapPrefabData>, wanted: impl Fn(&MapPrefabData) -> bool) -> usize {
+        prefab
+            .entities()
+            .position(|entity| entity.data().map_or(false, |data| wanted(data)))
+            .unwrap()
+    }
+
+    #[test]
+    fn clean_map() {
+        let (problems, warnings) = validate(&prefab(CLEAN));
+        assert!(problems.is_empty(), "{:?}", problems);
+        assert!(warnings.is_empty(), "{:?}", warnings);
+    }
+
+    #[test]
+    fn team_without_spawn_point() {
+        let (problems, _) = validate(&prefab(&CLEAN.replace('1', ".")));
+        assert_eq!(
+            problems,
+            vec!["team 1 has no spawn point, its bots would never spawn"]
+        );
+    }
+
+    #[test]
+    fn map_without_area() {
+        let (problems, _) = validate(&prefab(&CLEAN.replace('@', ".")));
+        assert_eq!(
+            problems,
+            vec!["no entity has an area, the camera's pause view needs one"]
+        );
+    }
+
+    #[test]
+    fn unreachable_spawn_point() {
+        let prefab = prefab(
+            "\
+###########
+#0.a@b.#1.#
+###########
+",
+        );
+        let spawn = index(&prefab, |data| {
+            data.spawn_point.as_ref().map_or(false, |s| s.team == 1)
+        });
+        let area = index(&prefab, |data| data.area.is_some());
+        let (problems, _) = validate(&prefab);
+        assert_eq!(
+            problems,
+            vec![format!(
+                "entities[{}]: the spawn point cannot reach the area, entities[{}]",
+                spawn, area
+            )]
+        );
+    }
+
+    #[test]
+    fn overlapping_walls() {
+        let mut prefab = prefab(CLEAN);
+        // the bottom row of the map is a single wall
+        let bottom = index(&prefab, |data| {
+            data.collider.as_ref().map_or(false, |c| c.width == 288.0)
+                && data.transform.as_ref().unwrap().translation().y == 16.0
+        });
+        prefab.add(
+            None,
+            Some(wall(
+                TileRect {
+                    x: 2,
+                    y: 0,
+                    w: 2,
+                    h: 1,
+                },
+                0,
+            )),
+        );
+        let added = prefab.entities().count() - 1;
+        let (problems, warnings) = validate(&prefab);
+        assert!(problems.is_empty(), "{:?}", problems);
+        assert_eq!(
+            warnings,
+            vec![format!(
+                "entities[{}] and entities[{}]: the walls overlap",
+                bottom, added
+            )]
+        );
+    }
+
+    #[test]
+    fn bad_checkpoints() {
+        let mut prefab = prefab(&CLEAN.replace('@', "."));
+        let mut path = AreaPath::new(vec![(112.0, 48.0), (176.0, 48.0)]);
+        path.checkpoints = vec![0.3, 1.5];
+        prefab.add(None, Some(area(144.0, 48.0, Some(path), &teams())));
+        let added = prefab.entities().count() - 1;
+        let (problems, _) = validate(&prefab);
+        assert_eq!(
+            problems,
+            vec![format!(
+                "entities[{}].area_path.checkpoints[1]: must be between 0 and 1 (got 1.5)",
+                added
+            )]
+        );
+    }
+}
@@ -1,5 +1,5 @@
 use amethyst::{
-    assets::{Handle, PrefabData, ProgressCounter},
+    assets::{Handle, Prefab, PrefabData, ProgressCounter},
     core::Transform,
     derive::PrefabData,
     ecs::prelude::*,
@@ -10,6 +10,8 @@ use serde_derive::{Deserialize, Serialize};
 
 use crate::ai::AI;
 use crate::components::*;
+use crate::resources::validate_item_kind;
+use crate::validation::{Validate, Validator};
 
 #[derive(PrefabData, Deserialize, Serialize, Default)]
 pub struct MyPrefabData {
@@ -25,8 +27,44 @@ pub struct MyPrefabData {
     pub reticle: Option<Reticle>,
     pub reticle_line: Option<ReticleLine>,
     pub shield: Option<Shield>,
+    pub buff_icon: Option<BuffIcon>,
     pub item: Option<Item>,
     pub particle: Option<Particle>,
+    pub explosion: Option<Explosion>,
+}
+
+impl Validate for Prefab<MyPrefabData> {
+    fn validate(&self, v: &mut Validator) {
+        v.check(
+            self.entities().next().is_some(),
+            "entities: at least one entity is required",
+        );
+        for (i, entity) in self.entities().enumerate() {
+            let data = match entity.data() {
+                Some(data) => data,
+                None => continue,
+            };
+            let field = |name: &str| format!("entities[{}].{}", i, name);
+            if let Some(sprite) = &data.sprite {
+                v.sprite(&field("sprite.sprite_number"), sprite.sprite_number);
+            }
+            if let Some(collider) = &data.collider {
+                v.positive(&field("collider.width"), collider.width);
+                v.positive(&field("collider.height"), collider.height);
+            }
+            if let Some(rigidbody) = &data.rigidbody {
+                v.non_negative(&field("rigidbody.drag"), rigidbody.drag);
+                v.non_negative(&field("rigidbody.bounciness"), rigidbody.bounciness);
+                v.non_negative(&field("rigidbody.friction"), rigidbody.friction);
+            }
+            if let Some(player) = &data.player {
+                v.non_negative(&field("player.hp"), player.hp);
+            }
+            if let Some(item) = &data.item {
+                validate_item_kind(v, &field("item.kind"), &item.kind);
+            }
+        }
+    }
 }
 
 #[derive(Clone, Deserialize, Serialize)]
@@ -63,9 +101,14 @@ pub struct MapPrefabData {
     pub transform: Option<Transform>,
     pub collider: Option<RectCollider>,
     pub sprite: Option<SpriteRenderPrefab>,
+    pub destructible: Option<Destructible>,
+    pub gadget: Option<Gadget>,
     pub spawn_point: Option<SpawnPoint>,
     pub area: Option<Area>,
+    pub area_path: Option<AreaPath>,
     pub area_target: Option<AreaTarget>,
+    pub flag: Option<Flag>,
+    pub map_info: Option<MapInfo>,
     #[serde(skip)]
     pub map: Map,
 }
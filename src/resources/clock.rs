@@ -0,0 +1,57 @@
+use crate::resources::ClockRules;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ClockPhase {
+    Regular,
+    // time is up with nobody ahead, the next scoring change decides
+    Overtime,
+    // overtime without item drops and with more damage
+    SuddenDeath,
+}
+
+pub struct MatchClock {
+    // frames left, None without a time limit
+    pub remaining: Option<u32>,
+    pub phase: ClockPhase,
+    // started by Select when the humans spawn
+    pub running: bool,
+    pub winner: Option<usize>,
+    // score when the overtime started or last changed without a winner
+    pub overtime_score: Vec<u32>,
+}
+
+impl MatchClock {
+    pub fn new(rules: &ClockRules) -> Self {
+        MatchClock {
+            remaining: rules.time_limit.map(|seconds| seconds * 60),
+            phase: ClockPhase::Regular,
+            running: false,
+            winner: None,
+            overtime_score: Vec::new(),
+        }
+    }
+
+    pub fn damage_multiplier(&self, rules: &ClockRules) -> f32 {
+        if self.phase == ClockPhase::SuddenDeath {
+            rules.sudden_death_damage
+        } else {
+            1.0
+        }
+    }
+
+    pub fn item_drops(&self) -> bool {
+        self.phase != ClockPhase::SuddenDeath
+    }
+
+    pub fn text(&self) -> String {
+        match (self.phase, self.remaining) {
+            (ClockPhase::Overtime, _) => "OVERTIME".to_string(),
+            (ClockPhase::SuddenDeath, _) => "SUDDEN DEATH".to_string(),
+            (ClockPhase::Regular, Some(frames)) => {
+                let seconds = (frames + 59) / 60;
+                format!("{}:{:02}", seconds / 60, seconds % 60)
+            }
+            (ClockPhase::Regular, None) => String::new(),
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use serde_derive::{Deserialize, Serialize};
+
+use crate::resources::{Contested, MatchRules, Score};
+
+// chosen in Select from the modes the map supports
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Debug)]
+pub enum GameMode {
+    // push the area towards the enemy side by standing in it
+    Area,
+    // a point per enemy killed
+    TeamDeathmatch,
+    // a point per scoring tick while holding the static area alone
+    KingOfTheHill,
+    // a point per enemy flag brought to the own flag
+    CaptureTheFlag,
+}
+
+impl Default for GameMode {
+    fn default() -> Self {
+        GameMode::Area
+    }
+}
+
+impl GameMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            GameMode::Area => "AREA",
+            GameMode::TeamDeathmatch => "TEAM DEATHMATCH",
+            GameMode::KingOfTheHill => "KING OF THE HILL",
+            GameMode::CaptureTheFlag => "CAPTURE THE FLAG",
+        }
+    }
+
+    pub fn uses_area(&self) -> bool {
+        *self == GameMode::Area || *self == GameMode::KingOfTheHill
+    }
+
+    pub fn uses_flags(&self) -> bool {
+        *self == GameMode::CaptureTheFlag
+    }
+
+    // score a team needs, None when the mode is won by lead
+    pub fn target(&self, rules: &MatchRules) -> Option<u32> {
+        match self {
+            GameMode::Area => None,
+            GameMode::TeamDeathmatch => Some(rules.modes.kills_to_win),
+            GameMode::KingOfTheHill => Some(rules.modes.hold_to_win),
+            GameMode::CaptureTheFlag => Some(rules.modes.captures_to_win),
+        }
+    }
+
+    // a point for the killer's team when it is an enemy it killed
+    pub fn score_kill(&self, score: &mut Score, killer_team: u32, victim_team: u32) {
+        if *self != GameMode::TeamDeathmatch || killer_team == victim_team {
+            return;
+        }
+        if let Some(score) = score.score.get_mut(killer_team as usize) {
+            *score += 1;
+        }
+    }
+
+    pub fn score_capture(&self, score: &mut Score, team: u32) {
+        if !self.uses_flags() {
+            return;
+        }
+        if let Some(score) = score.score.get_mut(team as usize) {
+            *score += 1;
+        }
+    }
+
+    // one scoring tick of the area, counts is how many tofus of each team stand in it
+    pub fn score_area(&self, score: &mut Score, counts: &[u32], contested_rule: Contested) {
+        let contested = counts.iter().filter(|&&count| count > 0).count() > 1;
+        score.contested = contested && self.uses_area();
+        let leader = area_leader(counts);
+        match self {
+            // the hill is only held by a team standing in it alone
+            GameMode::KingOfTheHill => {
+                if let (Some(team), false) = (leader, contested) {
+                    score.score[team] += 1;
+                }
+            }
+            GameMode::Area => {
+                for (team, &count) in counts.iter().enumerate() {
+                    let scores = match contested_rule {
+                        _ if !contested => true,
+                        Contested::EveryoneScores => true,
+                        Contested::MajorityScores => leader == Some(team),
+                        Contested::Blocked => false,
+                    };
+                    if scores {
+                        score.score[team] += count;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // held is the lead the area checkpoints keep, see AreaPath
+    pub fn winner(&self, score: &Score, rules: &MatchRules, held: i32) -> Option<usize> {
+        match self.target(rules) {
+            None => score.winner(rules.scoring.lead_to_win, held),
+            Some(target) => {
+                let mut teams = (0..score.score.len()).filter(|&team| score.score[team] >= target);
+                match (teams.next(), teams.next()) {
+                    // a tie at the target is played on
+                    (Some(team), None) => Some(team),
+                    _ => None,
+                }
+            }
+        }
+    }
+}
+
+// the team with the most tofus in the area, None on a tie or when it is empty
+pub fn area_leader(counts: &[u32]) -> Option<usize> {
+    let max = counts.iter().copied().max().unwrap_or(0);
+    let mut leaders = counts.iter().enumerate().filter(|(_, &count)| count == max);
+    match (leaders.next(), leaders.next()) {
+        (Some((team, _)), None) if max > 0 => Some(team),
+        _ => None,
+    }
+}
@@ -0,0 +1,142 @@
+use amethyst::{assets::Prefab, ecs::prelude::*};
+use serde::de::DeserializeOwned;
+use std::time::SystemTime;
+
+use crate::common::ronfile;
+use crate::prefab::*;
+use crate::resources::*;
+use crate::validation::{self, DataContext, DataError, Validate};
+
+#[derive(Clone, Copy, Debug)]
+enum ReloadTarget {
+    WeaponList,
+    ItemList,
+    MatchRules,
+    Prefab(PrefabKind),
+}
+
+#[derive(Clone)]
+struct WatchedFile {
+    path: String,
+    target: ReloadTarget,
+    modified: Option<SystemTime>,
+}
+
+// Polls balance data files and re-applies them when they change on disk.
+// Data that fails to parse is ignored and the previous data stays in use.
+// Unit prefabs are used by the next spawns, living units are left as they are.
+#[derive(Clone)]
+pub struct HotReload {
+    files: Vec<WatchedFile>,
+    context: DataContext,
+}
+
+impl HotReload {
+    pub fn new(world: &World, context: DataContext) -> Self {
+        let watch = |path: &str, target: ReloadTarget| WatchedFile {
+            path: path.to_string(),
+            target,
+            modified: ronfile::modified(path),
+        };
+        let mut files = vec![
+            watch("resources/weapon_list.ron", ReloadTarget::WeaponList),
+            watch("resources/item_list.ron", ReloadTarget::ItemList),
+            watch("resources/match_rules.ron", ReloadTarget::MatchRules),
+            watch(
+                "resources/player.ron",
+                ReloadTarget::Prefab(PrefabKind::Player),
+            ),
+        ];
+        for (i, team) in world.read_resource::<Teams>().iter().enumerate() {
+            files.push(watch(
+                &team.prefab,
+                ReloadTarget::Prefab(PrefabKind::Bot(i)),
+            ));
+        }
+        HotReload { files, context }
+    }
+
+    pub fn update(&mut self, world: &mut World) {
+        for file in self.files.iter_mut() {
+            let modified = ronfile::modified(&file.path);
+            if modified.is_none() || modified == file.modified {
+                continue;
+            }
+            file.modified = modified;
+
+            match file.target {
+                ReloadTarget::WeaponList => {
+                    match reload_weapons(world, &file.path, &self.context) {
+                        Ok(context) => {
+                            // items, rules and maps are checked against the new weapons from now on
+                            self.context = context;
+                            world.insert(self.context.clone());
+                            log::info!("Reloaded {}", file.path);
+                        }
+                        Err(e) => log::error!("Failed to reload, keeping previous data: {}", e),
+                    }
+                }
+                ReloadTarget::ItemList => {
+                    reload_resource::<ItemList>(world, &file.path, &self.context);
+                }
+                ReloadTarget::MatchRules => {
+                    reload_resource::<MatchRules>(world, &file.path, &self.context);
+                }
+                ReloadTarget::Prefab(kind) => {
+                    match validation::load::<Prefab<MyPrefabData>>(&file.path, &self.context) {
+                        Ok(prefab) => {
+                            let handle = load_prefab(world, prefab);
+                            world
+                                .write_resource::<RespawnHandler>()
+                                .set_prefab_handle(kind, handle);
+                            // tofus already on the field keep what they were spawned with
+                            log::info!("Reloaded {}, it applies from the next spawn", file.path);
+                        }
+                        Err(e) => log::error!("Failed to reload, keeping previous data: {}", e),
+                    }
+                }
+            }
+        }
+    }
+}
+
+// whether the new data is in use
+fn reload_resource<T>(world: &mut World, path: &str, context: &DataContext) -> bool
+where
+    T: DeserializeOwned + Validate + Send + Sync + 'static,
+{
+    match validation::load::<T>(path, context) {
+        Ok(data) => {
+            world.insert(data);
+            log::info!("Reloaded {}", path);
+            true
+        }
+        Err(e) => {
+            log::error!("Failed to reload, keeping previous data: {}", e);
+            false
+        }
+    }
+}
+
+// rules and items may name weapons by index, a shorter list must still cover them
+fn reload_weapons(
+    world: &mut World,
+    path: &str,
+    context: &DataContext,
+) -> Result<DataContext, DataError> {
+    let weapons = validation::load::<WeaponList>(path, context)?;
+    let mut context = context.clone();
+    context.weapon_count = weapons.len();
+    validation::check(
+        &*world.read_resource::<MatchRules>(),
+        "resources/match_rules.ron",
+        &context,
+    )?;
+    validation::check(
+        &*world.read_resource::<ItemList>(),
+        "resources/item_list.ron",
+        &context,
+    )?;
+    world.insert(weapons);
+    Ok(context)
+}
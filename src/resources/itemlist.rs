@@ -0,0 +1,99 @@
+use rand::prelude::*;
+use serde_derive::{Deserialize, Serialize};
+use std::mem::discriminant;
+use std::ops::Deref;
+
+use crate::components::{ItemKind, StatusKind};
+use crate::validation::{Validate, Validator};
+
+// what a tofu leaves behind when it dies
+#[derive(Deserialize, Serialize, Default)]
+pub struct ItemList {
+    pub list: Vec<ItemType>,
+}
+
+impl Deref for ItemList {
+    type Target = Vec<ItemType>;
+
+    fn deref(&self) -> &Vec<ItemType> {
+        &self.list
+    }
+}
+
+impl ItemList {
+    pub fn roll_drops(&self) -> Vec<&ItemType> {
+        let mut rng = thread_rng();
+        let mut drops = Vec::new();
+        for item_type in self.list.iter() {
+            if rng.gen::<f32>() < item_type.chance {
+                drops.extend((0..item_type.count).map(|_| item_type));
+            }
+        }
+        drops
+    }
+
+    // the sprite of the item that gives the buff
+    pub fn icon(&self, kind: &StatusKind) -> Option<usize> {
+        self.list
+            .iter()
+            .find(|item_type| {
+                item_type
+                    .kind
+                    .status()
+                    .map_or(false, |status| discriminant(&status) == discriminant(kind))
+            })
+            .map(|item_type| item_type.sprite)
+    }
+}
+
+impl Validate for ItemList {
+    fn validate(&self, v: &mut Validator) {
+        for (i, item_type) in self.list.iter().enumerate() {
+            let field = |name: &str| format!("list[{}].{}", i, name);
+            validate_item_kind(v, &field("kind"), &item_type.kind);
+            v.sprite(&field("sprite"), item_type.sprite);
+            if item_type.kind.status().is_some() {
+                v.check(
+                    item_type.duration > 0,
+                    format!("{}: must be greater than zero", field("duration")),
+                );
+            }
+            v.check(
+                item_type.chance >= 0.0 && item_type.chance <= 1.0,
+                format!(
+                    "{}: must be between 0 and 1 (got {})",
+                    field("chance"),
+                    item_type.chance
+                ),
+            );
+        }
+    }
+}
+
+pub fn validate_item_kind(v: &mut Validator, field: &str, kind: &ItemKind) {
+    match *kind {
+        ItemKind::Heal(value)
+        | ItemKind::SpeedBoost(value)
+        | ItemKind::DamageBoost(value)
+        | ItemKind::Overcharge(value) => v.non_negative(field, value),
+        ItemKind::RapidFire(value) => v.fraction(field, value),
+        ItemKind::WeaponSwap(index) => v.weapon(field, index),
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct ItemType {
+    pub kind: ItemKind,
+    pub sprite: usize,
+    // ticks the effect lasts, heals are instant
+    #[serde(default)]
+    pub duration: u32,
+    // dropped all at once
+    pub count: u32,
+    #[serde(default = "always")]
+    pub chance: f32,
+}
+
+fn always() -> f32 {
+    1.0
+}
@@ -0,0 +1,146 @@
+use amethyst::{
+    core::math::*,
+    input::{InputHandler, StringBindings},
+};
+use serde_derive::{Deserialize, Serialize};
+use std::ops::{Deref, DerefMut};
+
+use crate::validation::{Validate, Validator};
+
+pub const MAX_LOCAL_PLAYERS: usize = 4;
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Debug)]
+pub enum InputDevice {
+    KeyboardMouse,
+    // controller id, bound as "left_x_0", "shot_0" etc. in bindings_config.ron
+    Controller(u32),
+}
+
+#[derive(Deserialize, Serialize, Default, Clone)]
+pub struct LocalPlayers {
+    pub list: Vec<LocalPlayer>,
+}
+
+impl Deref for LocalPlayers {
+    type Target = Vec<LocalPlayer>;
+
+    fn deref(&self) -> &Vec<LocalPlayer> {
+        &self.list
+    }
+}
+
+impl DerefMut for LocalPlayers {
+    fn deref_mut(&mut self) -> &mut Vec<LocalPlayer> {
+        &mut self.list
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct LocalPlayer {
+    pub devices: Vec<InputDevice>,
+    #[serde(default)]
+    pub team: u32,
+    // chosen in Select and used for respawns
+    #[serde(skip)]
+    pub weapon: usize,
+}
+
+impl LocalPlayer {
+    pub fn uses_mouse(&self) -> bool {
+        self.devices.contains(&InputDevice::KeyboardMouse)
+    }
+
+    pub fn move_input(&self, input: &InputHandler<StringBindings>) -> Vector2<f32> {
+        let mut move_vec = Vector2::zeros();
+        for device in self.devices.iter() {
+            match device {
+                InputDevice::KeyboardMouse => move_vec += axis_xy(input, "move_x", "move_y"),
+                InputDevice::Controller(id) => {
+                    move_vec +=
+                        axis_xy(input, &format!("dpad_x_{}", id), &format!("dpad_y_{}", id));
+                    move_vec +=
+                        axis_xy(input, &format!("left_x_{}", id), &format!("left_y_{}", id));
+                }
+            }
+        }
+        move_vec
+    }
+
+    pub fn aim_input(&self, input: &InputHandler<StringBindings>) -> Vector2<f32> {
+        let mut aim_vec = Vector2::zeros();
+        for device in self.devices.iter() {
+            if let InputDevice::Controller(id) = device {
+                aim_vec += axis_xy(
+                    input,
+                    &format!("right_x_{}", id),
+                    &format!("right_y_{}", id),
+                );
+            }
+        }
+        aim_vec
+    }
+
+    pub fn shot(&self, input: &InputHandler<StringBindings>) -> bool {
+        self.action(input, "shot")
+    }
+
+    pub fn hold(&self, input: &InputHandler<StringBindings>) -> bool {
+        self.action(input, "hold")
+    }
+
+    fn action(&self, input: &InputHandler<StringBindings>, action: &str) -> bool {
+        self.devices.iter().any(|device| {
+            let name = match device {
+                InputDevice::KeyboardMouse => format!("{}_mouse", action),
+                InputDevice::Controller(id) => format!("{}_{}", action, id),
+            };
+            input.action_is_down(&name).unwrap_or(false)
+        })
+    }
+}
+
+fn axis_xy(input: &InputHandler<StringBindings>, x: &str, y: &str) -> Vector2<f32> {
+    Vector2::new(
+        input.axis_value(x).unwrap_or(0.0) as f32,
+        input.axis_value(y).unwrap_or(0.0) as f32,
+    )
+}
+
+impl Validate for LocalPlayers {
+    fn validate(&self, v: &mut Validator) {
+        v.check(
+            !self.list.is_empty() && self.list.len() <= MAX_LOCAL_PLAYERS,
+            format!(
+                "list: 1 to {} local players are supported (got {})",
+                MAX_LOCAL_PLAYERS,
+                self.list.len()
+            ),
+        );
+        let mut used = Vec::new();
+        for (i, player) in self.list.iter().enumerate() {
+            let field = |name: &str| format!("list[{}].{}", i, name);
+            v.check(
+                !player.devices.is_empty(),
+                format!("{}: must not be empty", field("devices")),
+            );
+            for device in player.devices.iter() {
+                if let InputDevice::Controller(id) = device {
+                    v.check(
+                        (*id as usize) < MAX_LOCAL_PLAYERS,
+                        format!("{}: controller {} is not bound", field("devices"), id),
+                    );
+                }
+                v.check(
+                    !used.contains(device),
+                    format!(
+                        "{}: {:?} is used by another player",
+                        field("devices"),
+                        device
+                    ),
+                );
+                used.push(*device);
+            }
+            v.team(&field("team"), player.team);
+        }
+    }
+}
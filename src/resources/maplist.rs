@@ -0,0 +1,88 @@
+use serde_derive::{Deserialize, Serialize};
+
+use crate::mapdata::{self, RANDOM_MAP};
+use crate::resources::GameMode;
+use crate::validation::{Validate, Validator};
+
+// the maps that can be chosen in Select and the ones played in turn between rounds,
+// see resources/maps/maps.ron
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct MapList {
+    pub maps: Vec<MapEntry>,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct MapEntry {
+    pub name: String,
+    pub path: String,
+    // sprite shown while the map is being chosen
+    pub preview: usize,
+    // the modes the map declares are used when empty
+    #[serde(default)]
+    pub modes: Vec<GameMode>,
+    // players per team on this map, match_rules.ron's team_size when None
+    #[serde(default)]
+    pub team_size: Option<u32>,
+    // played in turn with the other maps in the rotation, one per round
+    #[serde(default = "in_rotation")]
+    pub rotation: bool,
+}
+
+fn in_rotation() -> bool {
+    true
+}
+
+impl MapList {
+    // every generated map goes with the "random" entry
+    pub fn position(&self, path: &str) -> Option<usize> {
+        let random = mapdata::random_map_seed(path).is_some();
+        self.maps
+            .iter()
+            .position(|map| map.path == path || random && map.path == RANDOM_MAP)
+    }
+
+    pub fn find(&self, path: &str) -> Option<&MapEntry> {
+        self.position(path).map(|i| &self.maps[i])
+    }
+
+    pub fn first(&self) -> &str {
+        self.next("")
+    }
+
+    // the map of the rotation after `path`, the first one for maps that are not listed
+    pub fn next(&self, path: &str) -> &str {
+        let start = self.position(path).map_or(0, |i| i + 1);
+        (0..self.maps.len())
+            .map(|i| &self.maps[(start + i) % self.maps.len()])
+            .find(|map| map.rotation)
+            .map_or(RANDOM_MAP, |map| map.path.as_str())
+    }
+}
+
+impl Validate for MapList {
+    fn validate(&self, v: &mut Validator) {
+        v.check(!self.maps.is_empty(), "maps: at least one map is required");
+        v.check(
+            self.maps.iter().any(|map| map.rotation),
+            "maps: at least one map has to be in the rotation",
+        );
+        for (i, map) in self.maps.iter().enumerate() {
+            let field = |name: &str| format!("maps[{}].{}", i, name);
+            v.check(
+                !map.name.is_empty(),
+                format!("{}: must not be empty", field("name")),
+            );
+            v.check(
+                !map.path.is_empty(),
+                format!("{}: must not be empty", field("path")),
+            );
+            v.sprite(&field("preview"), map.preview);
+            if let Some(team_size) = map.team_size {
+                v.check(
+                    team_size > 0,
+                    format!("{}: must be greater than zero", field("team_size")),
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,256 @@
+use serde_derive::{Deserialize, Serialize};
+
+use crate::validation::{Validate, Validator};
+
+#[derive(Deserialize, Serialize, Default, Clone, Debug)]
+#[serde(default)]
+pub struct MatchRules {
+    pub friendly_fire: FriendlyFire,
+    pub bots: BotRules,
+    pub spawn: SpawnRules,
+    pub scoring: ScoringRules,
+    pub modes: ModeRules,
+    pub clock: ClockRules,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(default)]
+pub struct FriendlyFire {
+    pub enabled: bool,
+    pub damage_multiplier: f32,
+    pub self_damage: bool,
+}
+
+impl Default for FriendlyFire {
+    fn default() -> Self {
+        FriendlyFire {
+            enabled: false,
+            damage_multiplier: 0.5,
+            self_damage: false,
+        }
+    }
+}
+
+impl FriendlyFire {
+    // scale for damage and knockback of a hit, or None if the hit is ignored
+    pub fn hit_multiplier(
+        &self,
+        attacker_team: u32,
+        victim_team: u32,
+        is_self: bool,
+        explosive: bool,
+    ) -> Option<f32> {
+        if is_self {
+            if explosive && self.self_damage {
+                Some(self.damage_multiplier)
+            } else {
+                None
+            }
+        } else if attacker_team != victim_team {
+            Some(1.0)
+        } else if self.enabled {
+            Some(self.damage_multiplier)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(default)]
+pub struct BotRules {
+    // players per team, humans included unless fill is IgnoreHumans
+    pub team_size: u32,
+    // frames between spawn waves
+    pub spawn_interval: u64,
+    // bots spawned per team in each wave
+    pub spawn_per_wave: u32,
+    pub weapon_rotation: WeaponRotation,
+    pub fill: BotFill,
+}
+
+impl Default for BotRules {
+    fn default() -> Self {
+        BotRules {
+            team_size: 10,
+            spawn_interval: 128,
+            spawn_per_wave: 1,
+            weapon_rotation: WeaponRotation::RoundRobin,
+            fill: BotFill::CountHumans,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Debug)]
+pub enum WeaponRotation {
+    RoundRobin,
+    Random,
+    Fixed(usize),
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Debug)]
+pub enum BotFill {
+    // bots fill the slots humans leave free
+    CountHumans,
+    // every team gets team_size bots on top of its humans
+    IgnoreHumans,
+    // bots only on teams without humans
+    EmptyTeamsOnly,
+    NoBots,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(default)]
+pub struct SpawnRules {
+    pub policy: SpawnPolicy,
+    // ticks of invulnerability after spawning, the tofu cannot shoot meanwhile
+    pub protection: u32,
+}
+
+impl Default for SpawnRules {
+    fn default() -> Self {
+        SpawnRules {
+            policy: SpawnPolicy::RoundRobin,
+            protection: 90,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Debug)]
+pub enum SpawnPolicy {
+    Random,
+    RoundRobin,
+    // the point whose nearest enemy is the furthest away
+    FurthestFromEnemies,
+    // the point seen by the fewest enemies, walls block the view
+    SafestLineOfSight,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(default)]
+pub struct ScoringRules {
+    // a team wins when its score is this far ahead of every other team
+    pub lead_to_win: u32,
+    // frames between area scoring ticks
+    pub interval: u32,
+    pub contested: Contested,
+}
+
+impl Default for ScoringRules {
+    fn default() -> Self {
+        ScoringRules {
+            lead_to_win: 50,
+            interval: 60,
+            contested: Contested::EveryoneScores,
+        }
+    }
+}
+
+impl ScoringRules {
+    // progress along the area path, 0.0 when the second team has won,
+    // 1.0 when the first team has won
+    pub fn track_ratio(&self, lead: i32) -> f32 {
+        let ratio = lead as f32 / (self.lead_to_win * 2) as f32 + 0.5;
+        ratio.max(0.0).min(1.0)
+    }
+
+    // lead of the first team that puts the area at the ratio
+    pub fn track_lead(&self, ratio: f32) -> i32 {
+        ((ratio - 0.5) * (self.lead_to_win * 2) as f32).round() as i32
+    }
+}
+
+// scoring when more than one team stands in the area
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Debug)]
+pub enum Contested {
+    // every tofu scores a point for its team
+    EveryoneScores,
+    // only the team with the most tofus scores
+    MajorityScores,
+    // nobody scores
+    Blocked,
+}
+
+// win conditions of the modes other than Area
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(default)]
+pub struct ModeRules {
+    pub kills_to_win: u32,
+    // scoring ticks, see scoring.interval
+    pub hold_to_win: u32,
+    pub captures_to_win: u32,
+    // frames a dropped flag lies on the ground before going home
+    pub flag_return: u32,
+}
+
+impl Default for ModeRules {
+    fn default() -> Self {
+        ModeRules {
+            kills_to_win: 30,
+            hold_to_win: 60,
+            captures_to_win: 3,
+            flag_return: 600,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(default)]
+pub struct ClockRules {
+    // seconds, no clock when None
+    pub time_limit: Option<u32>,
+    // overtime becomes sudden death: no item drops and more damage
+    pub sudden_death: bool,
+    pub sudden_death_damage: f32,
+}
+
+impl Default for ClockRules {
+    fn default() -> Self {
+        ClockRules {
+            time_limit: None,
+            sudden_death: false,
+            sudden_death_damage: 2.0,
+        }
+    }
+}
+
+impl Validate for MatchRules {
+    fn validate(&self, v: &mut Validator) {
+        v.non_negative(
+            "friendly_fire.damage_multiplier",
+            self.friendly_fire.damage_multiplier,
+        );
+        v.check(
+            self.scoring.lead_to_win > 0,
+            "scoring.lead_to_win: must be greater than zero",
+        );
+        v.check(
+            self.scoring.interval > 0,
+            "scoring.interval: must be greater than zero",
+        );
+        v.check(
+            self.modes.kills_to_win > 0,
+            "modes.kills_to_win: must be greater than zero",
+        );
+        v.check(
+            self.modes.hold_to_win > 0,
+            "modes.hold_to_win: must be greater than zero",
+        );
+        v.check(
+            self.modes.captures_to_win > 0,
+            "modes.captures_to_win: must be greater than zero",
+        );
+        v.check(
+            self.clock.time_limit != Some(0),
+            "clock.time_limit: must be greater than zero or None",
+        );
+        v.non_negative("clock.sudden_death_damage", self.clock.sudden_death_damage);
+        v.check(
+            self.bots.spawn_interval > 0,
+            "bots.spawn_interval: must be greater than zero",
+        );
+        if let WeaponRotation::Fixed(index) = self.bots.weapon_rotation {
+            v.weapon("bots.weapon_rotation", index);
+        }
+    }
+}
@@ -1,7 +1,31 @@
+pub mod clock;
+pub mod gamemode;
+#[cfg(not(feature = "include_resources"))]
+pub mod hotreload;
+pub mod itemlist;
+pub mod localplayers;
+pub mod maplist;
+pub mod matchrules;
+pub mod navgrid;
 pub mod respawn;
 pub mod score;
+pub mod series;
+pub mod stats;
+pub mod teams;
 pub mod weaponlist;
 
+pub use clock::*;
+pub use gamemode::*;
+#[cfg(not(feature = "include_resources"))]
+pub use hotreload::*;
+pub use itemlist::*;
+pub use localplayers::*;
+pub use maplist::*;
+pub use matchrules::*;
+pub use navgrid::*;
 pub use respawn::*;
 pub use score::*;
+pub use series::*;
+pub use stats::*;
+pub use teams::*;
 pub use weaponlist::*;
@@ -0,0 +1,241 @@
+use amethyst::core::math::Vector2;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, VecDeque};
+
+// cells a player just fits in
+const CELL: f32 = 16.0;
+// straight and diagonal steps between cells
+const STEP: u32 = 10;
+const DIAGONAL_STEP: u32 = 14;
+
+// a rectangle as its center and size
+pub type NavRect = (Vector2<f32>, Vector2<f32>);
+
+// where the AI can walk, built from the walls and hazards of the map
+#[derive(Default)]
+pub struct NavGrid {
+    left: f32,
+    bottom: f32,
+    cols: usize,
+    rows: usize,
+    blocked: Vec<bool>,
+    // added to the steps into a cell, paths go around hazards when it is worth it
+    costs: Vec<u32>,
+}
+
+impl NavGrid {
+    // the grid covers the walls with a free cell around
+    pub fn new(walls: &[NavRect], hazards: &[(NavRect, u32)]) -> Self {
+        NavGrid::covering(walls, hazards, &[])
+    }
+
+    // like new, and wide enough for the points as well, so open maps can be walked around
+    pub fn covering(
+        walls: &[NavRect],
+        hazards: &[(NavRect, u32)],
+        points: &[Vector2<f32>],
+    ) -> Self {
+        if walls.is_empty() && points.is_empty() {
+            return NavGrid::default();
+        }
+        let mut min = Vector2::repeat(std::f32::INFINITY);
+        let mut max = Vector2::repeat(std::f32::NEG_INFINITY);
+        for (center, size) in walls.iter() {
+            min = min.inf(&(center - size / 2.0));
+            max = max.sup(&(center + size / 2.0));
+        }
+        for point in points.iter() {
+            min = min.inf(point);
+            max = max.sup(point);
+        }
+        let left = min.x - CELL;
+        let bottom = min.y - CELL;
+        let cols = ((max.x + CELL - left) / CELL).ceil() as usize;
+        let rows = ((max.y + CELL - bottom) / CELL).ceil() as usize;
+
+        let overlaps = |index: usize, (center, size): &NavRect| {
+            let x = left + (index % cols) as f32 * CELL;
+            let y = bottom + (index / cols) as f32 * CELL;
+            let (min, max) = (center - size / 2.0, center + size / 2.0);
+            min.x < x + CELL && x < max.x && min.y < y + CELL && y < max.y
+        };
+        let blocked = (0..rows * cols)
+            .map(|index| walls.iter().any(|wall| overlaps(index, wall)))
+            .collect();
+        let costs = (0..rows * cols)
+            .map(|index| {
+                hazards
+                    .iter()
+                    .filter(|(rect, _)| overlaps(index, rect))
+                    .map(|&(_, cost)| cost)
+                    .sum()
+            })
+            .collect();
+
+        NavGrid {
+            left,
+            bottom,
+            cols,
+            rows,
+            blocked,
+            costs,
+        }
+    }
+
+    fn cell(&self, position: Vector2<f32>) -> usize {
+        let col = ((position.x - self.left) / CELL).max(0.0) as usize;
+        let row = ((position.y - self.bottom) / CELL).max(0.0) as usize;
+        row.min(self.rows - 1) * self.cols + col.min(self.cols - 1)
+    }
+
+    fn center(&self, index: usize) -> Vector2<f32> {
+        Vector2::new(
+            self.left + ((index % self.cols) as f32 + 0.5) * CELL,
+            self.bottom + ((index / self.cols) as f32 + 0.5) * CELL,
+        )
+    }
+
+    // whether the straight line between the two crosses no wall or hazard
+    pub fn clear(&self, from: Vector2<f32>, to: Vector2<f32>) -> bool {
+        if self.blocked.is_empty() {
+            return true;
+        }
+        let steps = ((to - from).norm() / (CELL / 2.0)).ceil() as usize;
+        (1..steps).all(|step| {
+            let position = from + (to - from) * (step as f32 / steps as f32);
+            let cell = self.cell(position);
+            !self.blocked[cell] && self.costs[cell] == 0
+        })
+    }
+
+    // the cell centers from `from` to `to` around the walls, None when they are not connected
+    pub fn path(&self, from: Vector2<f32>, to: Vector2<f32>) -> Option<Vec<Vector2<f32>>> {
+        if self.blocked.is_empty() {
+            return Some(vec![to]);
+        }
+        let (start, goal) = (self.cell(from), self.cell(to));
+        let col_row = |index: usize| ((index % self.cols) as i32, (index / self.cols) as i32);
+        let index_of = |col: i32, row: i32| {
+            let inside =
+                col >= 0 && row >= 0 && (col as usize) < self.cols && (row as usize) < self.rows;
+            if inside {
+                Some(row as usize * self.cols + col as usize)
+            } else {
+                None
+            }
+        };
+        let free = |col: i32, row: i32| index_of(col, row).map_or(false, |i| !self.blocked[i]);
+        let estimate = |index: usize| {
+            let ((col, row), (goal_col, goal_row)) = (col_row(index), col_row(goal));
+            let (dx, dy) = ((col - goal_col).abs() as u32, (row - goal_row).abs() as u32);
+            STEP * dx.max(dy) + (DIAGONAL_STEP - STEP) * dx.min(dy)
+        };
+
+        let mut cost = vec![std::u32::MAX; self.blocked.len()];
+        let mut came_from = vec![None; self.blocked.len()];
+        let mut open = BinaryHeap::new();
+        cost[start] = 0;
+        open.push(Reverse((estimate(start), start)));
+        while let Some(Reverse((_, index))) = open.pop() {
+            if index == goal {
+                let mut path = vec![to];
+                let mut index = goal;
+                while let Some(previous) = came_from[index] {
+                    if previous != start {
+                        path.push(self.center(previous));
+                    }
+                    index = previous;
+                }
+                path.reverse();
+                return Some(path);
+            }
+            let (col, row) = col_row(index);
+            for dx in -1..=1 {
+                for dy in -1..=1 {
+                    let next = match index_of(col + dx, row + dy) {
+                        Some(next) if (dx, dy) != (0, 0) => next,
+                        _ => continue,
+                    };
+                    // the goal may be against a wall, and no corner is cut
+                    if self.blocked[next] && next != goal {
+                        continue;
+                    }
+                    if dx != 0 && dy != 0 && !(free(col + dx, row) && free(col, row + dy)) {
+                        continue;
+                    }
+                    let step = if dx != 0 && dy != 0 {
+                        DIAGONAL_STEP
+                    } else {
+                        STEP
+                    };
+                    let next_cost = cost[index] + step + self.costs[next];
+                    if next_cost < cost[next] {
+                        cost[next] = next_cost;
+                        came_from[next] = Some(index);
+                        open.push(Reverse((next_cost + estimate(next), next)));
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    // for every point, whether it can be walked to from `from` or is on a wall cell
+    pub fn reaches(&self, from: Vector2<f32>, points: &[Vector2<f32>]) -> Vec<bool> {
+        if self.blocked.is_empty() {
+            return vec![true; points.len()];
+        }
+        let mut reached = vec![false; self.blocked.len()];
+        let mut queue = VecDeque::new();
+        let start = self.cell(from);
+        if !self.blocked[start] {
+            reached[start] = true;
+            queue.push_back(start);
+        }
+        while let Some(index) = queue.pop_front() {
+            let (col, row) = (index % self.cols, index / self.cols);
+            let mut neighbours = Vec::new();
+            if col > 0 {
+                neighbours.push(index - 1);
+            }
+            if col + 1 < self.cols {
+                neighbours.push(index + 1);
+            }
+            if row > 0 {
+                neighbours.push(index - self.cols);
+            }
+            if row + 1 < self.rows {
+                neighbours.push(index + self.cols);
+            }
+            for next in neighbours {
+                if !self.blocked[next] && !reached[next] {
+                    reached[next] = true;
+                    queue.push_back(next);
+                }
+            }
+        }
+        points
+            .iter()
+            .map(|&point| {
+                let cell = self.cell(point);
+                reached[cell] || self.blocked[cell]
+            })
+            .collect()
+    }
+
+    // the furthest point of the path that can be walked to in a straight line
+    pub fn waypoint(&self, from: Vector2<f32>, to: Vector2<f32>) -> Vector2<f32> {
+        if self.clear(from, to) {
+            return to;
+        }
+        self.path(from, to)
+            .and_then(|path| {
+                let first = path[0];
+                path.into_iter()
+                    .take_while(|&point| self.clear(from, point))
+                    .last()
+                    .or(Some(first))
+            })
+            .unwrap_or(to)
+    }
+}
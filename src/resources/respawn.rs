@@ -1,139 +1,354 @@
-use amethyst::{assets::*, core::math::*, core::Transform, ecs::prelude::*};
-
-#[cfg(feature = "include_resources")]
-use amethyst::prelude::Config;
+use amethyst::{
+    assets::*,
+    core::math::*,
+    core::Transform,
+    ecs::prelude::*,
+    renderer::{palette::Srgba, resources::Tint},
+};
+use rand::prelude::*;
 
+use crate::common::collision2d::segment_intersects_rect;
 use crate::components::*;
 use crate::prefab::*;
+use crate::resources::{
+    BotFill, LocalPlayers, MapList, MatchRules, Series, SpawnPolicy, Teams, WeaponList,
+    WeaponRotation,
+};
+#[cfg(feature = "include_resources")]
+use crate::validation::ValidationError;
+use crate::validation::{self, DataContext, DataError};
+
+#[derive(Clone, Copy, Debug)]
+pub enum PrefabKind {
+    Player,
+    Bot(usize),
+}
 
 #[derive(Clone)]
 pub struct RespawnHandler {
     player_prefab_handle: Option<Handle<Prefab<MyPrefabData>>>,
-    ai_prefab_handle: Option<Handle<Prefab<MyPrefabData>>>,
-    enemy_prefab_handle: Option<Handle<Prefab<MyPrefabData>>>,
-    ai_weapon: usize,
-    enemy_weapon: usize,
+    bot_prefab_handles: Vec<Option<Handle<Prefab<MyPrefabData>>>>,
+    bot_weapons: Vec<usize>,
+    spawn_cursors: Vec<usize>,
 }
 
 impl RespawnHandler {
-    pub fn initialize(world: &mut World) -> Self {
-        RespawnHandler {
-            player_prefab_handle: world.exec(|loader: PrefabLoader<'_, MyPrefabData>| {
-                #[cfg(feature = "include_resources")]
-                return Some(loader.load_from_data(
-                    Config::load_bytes(include_bytes!("../resources/player.ron")).unwrap(),
-                    (),
-                ));
-                #[cfg(not(feature = "include_resources"))]
-                return Some(loader.load("resources/player.ron", RonFormat, ()));
-            }),
-            ai_prefab_handle: world.exec(|loader: PrefabLoader<'_, MyPrefabData>| {
-                #[cfg(feature = "include_resources")]
-                return Some(loader.load_from_data(
-                    Config::load_bytes(include_bytes!("../resources/ai.ron")).unwrap(),
-                    (),
-                ));
-                #[cfg(not(feature = "include_resources"))]
-                return Some(loader.load("resources/ai.ron", RonFormat, ()));
-            }),
-            enemy_prefab_handle: world.exec(|loader: PrefabLoader<'_, MyPrefabData>| {
-                #[cfg(feature = "include_resources")]
-                return Some(loader.load_from_data(
-                    Config::load_bytes(include_bytes!("../resources/enemy.ron")).unwrap(),
-                    (),
-                ));
-                #[cfg(not(feature = "include_resources"))]
-                return Some(loader.load("resources/enemy.ron", RonFormat, ()));
-            }),
-            ai_weapon: 0,
-            enemy_weapon: 0,
+    pub fn initialize(world: &mut World, context: &DataContext) -> Result<Self, DataError> {
+        let player = load_unit_prefab("resources/player.ron", context)?;
+        let mut bot_prefab_handles = Vec::new();
+        let prefab_paths: Vec<String> = world
+            .read_resource::<Teams>()
+            .iter()
+            .map(|team| team.prefab.clone())
+            .collect();
+        for path in prefab_paths.iter() {
+            let bot = load_unit_prefab(path, context)?;
+            bot_prefab_handles.push(Some(load_prefab(world, bot)));
         }
+
+        Ok(RespawnHandler {
+            player_prefab_handle: Some(load_prefab(world, player)),
+            bot_weapons: vec![0; bot_prefab_handles.len()],
+            spawn_cursors: vec![0; bot_prefab_handles.len()],
+            bot_prefab_handles,
+        })
     }
 
-    pub fn respawn_npc(&mut self, world: &mut World) {
-        let (mut army_count, mut enemy_count) = (0u32, 0u32);
-        for player in world.read_storage::<Player>().join() {
-            if player.team == 0 {
-                army_count += 1;
-            } else {
-                enemy_count += 1;
+    pub fn set_prefab_handle(&mut self, kind: PrefabKind, handle: Handle<Prefab<MyPrefabData>>) {
+        match kind {
+            PrefabKind::Player => self.player_prefab_handle = Some(handle),
+            PrefabKind::Bot(team) => {
+                if let Some(bot) = self.bot_prefab_handles.get_mut(team) {
+                    *bot = Some(handle);
+                }
             }
         }
+    }
 
-        if army_count < 10 {
-            if let Some(point) = get_spawn_point(world, 0) {
-                let mut transform = Transform::default();
-                transform.set_translation_xyz(point.x, point.y, 0.0);
-                world
-                    .create_entity()
-                    .with(
-                        self.ai_prefab_handle
-                            .clone()
-                            .expect("Failed to get prefab handle??"),
-                    )
-                    .with(transform)
-                    .with(Player {
-                        weapon: self.ai_weapon,
-                        ..Default::default()
-                    })
-                    .build();
-                self.ai_weapon = (self.ai_weapon + 1) % 3;
+    pub fn respawn_npc(&mut self, world: &mut World) {
+        let weapon_count = world.read_resource::<WeaponList>().len();
+        let mut bots = world.read_resource::<MatchRules>().bots.clone();
+        // the map may call for smaller or larger teams
+        {
+            let series = world.read_resource::<Series>();
+            let map_list = world.read_resource::<MapList>();
+            if let Some(team_size) = map_list.find(&series.map).and_then(|map| map.team_size) {
+                bots.team_size = team_size;
             }
         }
-        if enemy_count < 10 {
-            if let Some(point) = get_spawn_point(world, 1) {
-                let mut transform = Transform::default();
-                transform.set_translation_xyz(point.x, point.y, 0.0);
-                world
-                    .create_entity()
-                    .with(
-                        self.enemy_prefab_handle
-                            .clone()
-                            .expect("Failed to get prefab handle??"),
-                    )
-                    .with(transform)
-                    .with(Player {
-                        team: 1,
-                        weapon: self.enemy_weapon,
-                        ..Default::default()
-                    })
-                    .build();
-                self.enemy_weapon = (self.enemy_weapon + 1) % 3;
+        let team_count = self.bot_prefab_handles.len();
+        let mut bot_counts = vec![0u32; team_count];
+        let mut human_counts = vec![0u32; team_count];
+        let mut bot_slots = vec![Vec::new(); team_count];
+        {
+            let players = world.read_storage::<Player>();
+            let playables = world.read_storage::<Playable>();
+            for (player, playable) in (&players, playables.maybe()).join() {
+                let counts = if playable.is_some() {
+                    &mut human_counts
+                } else {
+                    &mut bot_counts
+                };
+                if let Some(count) = counts.get_mut(player.team as usize) {
+                    *count += 1;
+                }
+                if let (None, Some(slots)) = (playable, bot_slots.get_mut(player.team as usize)) {
+                    slots.push(player.bot_slot);
+                }
             }
         }
-    }
 
-    pub fn respawn_player(&mut self, world: &mut World, weapon: usize) {
-        if world.read_storage::<Playable>().join().next().is_none() {
-            if let Some(point) = get_spawn_point(world, 0) {
+        for team in 0..team_count {
+            let bot_limit = match bots.fill {
+                BotFill::CountHumans => bots.team_size.saturating_sub(human_counts[team]),
+                BotFill::IgnoreHumans => bots.team_size,
+                BotFill::EmptyTeamsOnly if human_counts[team] == 0 => bots.team_size,
+                BotFill::EmptyTeamsOnly | BotFill::NoBots => 0,
+            };
+            let spawn_count = bot_limit
+                .saturating_sub(bot_counts[team])
+                .min(bots.spawn_per_wave);
+            for _ in 0..spawn_count {
+                let point = match self.spawn_point(world, team as u32) {
+                    Some(point) => point,
+                    None => break,
+                };
+                let weapon = match bots.weapon_rotation {
+                    WeaponRotation::RoundRobin => {
+                        let weapon = self.bot_weapons[team] % weapon_count;
+                        self.bot_weapons[team] = (weapon + 1) % weapon_count;
+                        weapon
+                    }
+                    WeaponRotation::Random => thread_rng().gen_range(0, weapon_count),
+                    WeaponRotation::Fixed(weapon) => weapon,
+                };
+                // a respawned bot takes the first slot no living bot of its team has
+                let bot_slot = (0..)
+                    .find(|slot| !bot_slots[team].contains(slot))
+                    .unwrap_or(0);
+                bot_slots[team].push(bot_slot);
                 let mut transform = Transform::default();
                 transform.set_translation_xyz(point.x, point.y, 0.0);
+                let tint = team_tint(world, team as u32);
+                let status = spawn_status(world);
                 world
                     .create_entity()
                     .with(
-                        self.player_prefab_handle
+                        self.bot_prefab_handles[team]
                             .clone()
                             .expect("Failed to get prefab handle??"),
                     )
                     .with(transform)
+                    .with(tint)
+                    .with(status)
                     .with(Player {
-                        weapon: weapon,
+                        team: team as u32,
+                        weapon,
+                        bot_slot,
                         ..Default::default()
                     })
                     .build();
             }
         }
     }
+
+    pub fn respawn_player(&mut self, world: &mut World, id: usize) {
+        let alive = world
+            .read_storage::<Playable>()
+            .join()
+            .any(|playable| playable.id == id);
+        if alive {
+            return;
+        }
+        let (team, weapon) = match world.read_resource::<LocalPlayers>().get(id) {
+            Some(local) => (local.team, local.weapon),
+            None => return,
+        };
+        if let Some(point) = self.spawn_point(world, team) {
+            let mut transform = Transform::default();
+            transform.set_translation_xyz(point.x, point.y, 0.0);
+            let tint = team_tint(world, team);
+            let status = spawn_status(world);
+            world
+                .create_entity()
+                .with(
+                    self.player_prefab_handle
+                        .clone()
+                        .expect("Failed to get prefab handle??"),
+                )
+                .with(transform)
+                .with(tint)
+                .with(status)
+                .with(Playable { id })
+                .with(Player {
+                    team,
+                    weapon,
+                    ..Default::default()
+                })
+                .build();
+        }
+    }
+
+    fn spawn_point(&mut self, world: &mut World, team: u32) -> Option<Vector2<f32>> {
+        let candidates = spawn_candidates(world, team);
+        if candidates.len() <= 1 {
+            return candidates.first().copied();
+        }
+        let policy = world.read_resource::<MatchRules>().spawn.policy;
+        let index = match policy {
+            SpawnPolicy::Random => thread_rng().gen_range(0, candidates.len()),
+            SpawnPolicy::RoundRobin => {
+                let cursor = &mut self.spawn_cursors[team as usize];
+                *cursor = (*cursor + 1) % candidates.len();
+                *cursor
+            }
+            SpawnPolicy::FurthestFromEnemies => {
+                let enemies = enemy_positions(world, team);
+                furthest(&candidates, |point| nearest_distance(point, &enemies))
+            }
+            SpawnPolicy::SafestLineOfSight => {
+                let enemies = enemy_positions(world, team);
+                let walls = walls(world);
+                // fewest enemies in sight first, then the furthest nearest enemy
+                furthest(&candidates, |point| {
+                    let seen_by = enemies
+                        .iter()
+                        .filter(|&&enemy| {
+                            !walls.iter().any(|&(center, size)| {
+                                segment_intersects_rect(point, enemy, center, size)
+                            })
+                        })
+                        .count();
+                    nearest_distance(point, &enemies).min(10000.0) - seen_by as f32 * 10000.0
+                })
+            }
+        };
+        candidates.get(index).copied()
+    }
 }
 
-fn get_spawn_point(world: &mut World, team: u32) -> Option<Vector2<f32>> {
-    world.exec(
-        |(spawnpoints, transforms): (ReadStorage<'_, SpawnPoint>, WriteStorage<'_, Transform>)| {
+#[cfg(not(feature = "include_resources"))]
+fn load_unit_prefab(path: &str, context: &DataContext) -> Result<Prefab<MyPrefabData>, DataError> {
+    validation::load(path, context)
+}
+
+// the unit prefabs built into the binary, the teams are checked against them
+#[cfg(feature = "include_resources")]
+pub fn embedded_unit_prefab(path: &str) -> Option<&'static [u8]> {
+    match path {
+        "resources/player.ron" => Some(include_bytes!("../../resources/player.ron")),
+        "resources/ai.ron" => Some(include_bytes!("../../resources/ai.ron")),
+        "resources/enemy.ron" => Some(include_bytes!("../../resources/enemy.ron")),
+        _ => None,
+    }
+}
+
+#[cfg(feature = "include_resources")]
+fn load_unit_prefab(path: &str, context: &DataContext) -> Result<Prefab<MyPrefabData>, DataError> {
+    let bytes = embedded_unit_prefab(path).ok_or_else(|| ValidationError {
+        source: path.to_string(),
+        problems: vec!["is not embedded, add it to embedded_unit_prefab".to_string()],
+    })?;
+    validation::load_bytes(bytes, path, context)
+}
+
+pub fn load_prefab(
+    world: &mut World,
+    prefab: Prefab<MyPrefabData>,
+) -> Handle<Prefab<MyPrefabData>> {
+    world.exec(|loader: PrefabLoader<'_, MyPrefabData>| loader.load_from_data(prefab, ()))
+}
+
+fn team_tint(world: &World, team: u32) -> Tint {
+    let (r, g, b, a) = world
+        .read_resource::<Teams>()
+        .get(team as usize)
+        .map_or((1.0, 1.0, 1.0, 1.0), |team| team.color);
+    Tint(Srgba::new(r, g, b, a))
+}
+
+fn spawn_status(world: &World) -> StatusEffects {
+    let protection = world.read_resource::<MatchRules>().spawn.protection;
+    let mut status = StatusEffects::default();
+    if protection > 0 {
+        status.add(StatusEffect {
+            kind: StatusKind::SpawnProtection,
+            duration: protection,
+        });
+    }
+    status
+}
+
+fn spawn_candidates(world: &mut World, team: u32) -> Vec<Vector2<f32>> {
+    let points: Vec<Vector2<f32>> = world.exec(
+        |(spawnpoints, transforms): (ReadStorage<'_, SpawnPoint>, ReadStorage<'_, Transform>)| {
             (&spawnpoints, &transforms)
                 .join()
                 .filter(|(spawnpoint, _)| spawnpoint.team == team)
-                .next()
                 .map(|(_, transform)| transform.translation().xy())
+                .collect()
+        },
+    );
+    if !points.is_empty() {
+        return points;
+    }
+    world
+        .read_resource::<Teams>()
+        .get(team as usize)
+        .map_or(Vec::new(), |team| {
+            team.spawn_points
+                .iter()
+                .map(|&(x, y)| Vector2::new(x, y))
+                .collect()
+        })
+}
+
+fn enemy_positions(world: &mut World, team: u32) -> Vec<Vector2<f32>> {
+    world.exec(
+        |(players, transforms): (ReadStorage<'_, Player>, ReadStorage<'_, Transform>)| {
+            (&players, &transforms)
+                .join()
+                .filter(|(player, _)| player.team != team)
+                .map(|(_, transform)| transform.translation().xy())
+                .collect()
+        },
+    )
+}
+
+fn walls(world: &mut World) -> Vec<(Vector2<f32>, Vector2<f32>)> {
+    world.exec(
+        |(colliders, transforms): (ReadStorage<'_, RectCollider>, ReadStorage<'_, Transform>)| {
+            (&colliders, &transforms)
+                .join()
+                .filter(|(collider, _)| collider.tag == "Wall")
+                .map(|(collider, transform)| {
+                    (
+                        transform.translation().xy(),
+                        Vector2::new(collider.width, collider.height),
+                    )
+                })
+                .collect()
         },
     )
 }
+
+fn nearest_distance(point: Vector2<f32>, others: &[Vector2<f32>]) -> f32 {
+    others
+        .iter()
+        .map(|other| (other - point).norm())
+        .fold(std::f32::INFINITY, f32::min)
+}
+
+// index of the candidate with the highest score
+fn furthest(candidates: &[Vector2<f32>], score: impl Fn(Vector2<f32>) -> f32) -> usize {
+    let mut best = 0;
+    let mut best_score = std::f32::NEG_INFINITY;
+    for (i, &point) in candidates.iter().enumerate() {
+        let point_score = score(point);
+        if point_score > best_score {
+            best = i;
+            best_score = point_score;
+        }
+    }
+    best
+}
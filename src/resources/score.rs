@@ -1,3 +1,45 @@
 pub struct Score {
     pub score: Vec<u32>,
+    // more than one team stood in the area at the last scoring tick
+    pub contested: bool,
+}
+
+impl Score {
+    pub fn new(team_count: usize) -> Self {
+        Score {
+            score: vec![0; team_count],
+            contested: false,
+        }
+    }
+
+    // score of the team minus the best score among the other teams
+    pub fn lead(&self, team: usize) -> i32 {
+        let best_other = self
+            .score
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i != team)
+            .map(|(_, score)| *score as i32)
+            .max()
+            .unwrap_or(0);
+        self.score[team] as i32 - best_other
+    }
+
+    // lead counting what an area checkpoint holds for the first team of two
+    pub fn held_lead(&self, team: usize, held: i32) -> i32 {
+        match (team, self.score.len()) {
+            (0, 2) => self.lead(0) + held,
+            (1, 2) => self.lead(1) - held,
+            _ => self.lead(team),
+        }
+    }
+
+    // the team with the highest score, None on a tie
+    pub fn leader(&self, held: i32) -> Option<usize> {
+        (0..self.score.len()).find(|&team| self.held_lead(team, held) > 0)
+    }
+
+    pub fn winner(&self, lead_to_win: u32, held: i32) -> Option<usize> {
+        (0..self.score.len()).find(|&team| self.held_lead(team, held) >= lead_to_win as i32)
+    }
 }
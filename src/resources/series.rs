@@ -0,0 +1,128 @@
+use serde_derive::{Deserialize, Serialize};
+
+use crate::mapdata::{self, RANDOM_MAP};
+use crate::resources::MapList;
+use crate::validation::{Validate, Validator};
+
+// how a series is played, see resources/series.ron. there is no playlist file,
+// the maps with rotation set in resources/maps/maps.ron are played in its order
+#[derive(Deserialize, Serialize, Clone)]
+pub struct SeriesRules {
+    // rounds in a series, the first team to win more than half takes it,
+    // otherwise the most wins after the last round
+    pub best_of: u32,
+    // frames between rounds
+    pub intermission: u32,
+}
+
+impl Validate for SeriesRules {
+    fn validate(&self, v: &mut Validator) {
+        v.check(self.best_of > 0, "best_of: must be greater than zero");
+    }
+}
+
+pub struct Series {
+    // counted from 1
+    pub round: u32,
+    pub wins: Vec<u32>,
+    // the team that won the last round, it takes the series from teams with as many wins
+    pub last_win: Option<usize>,
+    // path of the map being played
+    pub map: String,
+    // the last map that loaded, played again when another one cannot be
+    pub last_valid: String,
+}
+
+impl Series {
+    pub fn new(team_count: usize, map: &str) -> Self {
+        Series {
+            round: 1,
+            wins: vec![0; team_count],
+            last_win: None,
+            map: map.to_string(),
+            last_valid: map.to_string(),
+        }
+    }
+
+    // starts a new series, the map rotation carries on
+    pub fn restart(&mut self) {
+        self.round = 1;
+        for wins in self.wins.iter_mut() {
+            *wins = 0;
+        }
+        self.last_win = None;
+    }
+
+    pub fn win(&mut self, team: usize) {
+        self.wins[team] += 1;
+        self.last_win = Some(team);
+    }
+
+    // a team with more than half of the rounds, or the most wins once they are all played
+    pub fn winner(&self, best_of: u32) -> Option<usize> {
+        let majority = (0..self.wins.len()).find(|&team| self.wins[team] * 2 > best_of);
+        if majority.is_some() || self.round < best_of {
+            return majority;
+        }
+        let most = self.wins.iter().copied().max()?;
+        self.last_win
+            .filter(|&team| self.wins[team] == most)
+            .or_else(|| (0..self.wins.len()).find(|&team| self.wins[team] == most))
+    }
+
+    // the maps of the rotation follow each other, a map chosen off the rotation
+    // is followed by the next one in the list, a random map by another random one
+    pub fn next_map(&mut self, map_list: &MapList) -> &str {
+        let random = mapdata::random_map_seed(&self.map).is_some();
+        let rotated = map_list.find(&self.map).map_or(false, |map| map.rotation);
+        let next = if random && !rotated {
+            RANDOM_MAP
+        } else {
+            map_list.next(&self.map)
+        };
+        self.map = mapdata::resolve_map(next);
+        &self.map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(series: &mut Series, winners: &[usize], best_of: u32) -> Option<usize> {
+        let mut winner = None;
+        for (i, &team) in winners.iter().enumerate() {
+            assert!(
+                winner.is_none(),
+                "the series was over before round {}",
+                i + 1
+            );
+            series.round = i as u32 + 1;
+            series.win(team);
+            winner = series.winner(best_of);
+        }
+        winner
+    }
+
+    #[test]
+    fn majority_ends_the_series_early() {
+        let mut series = Series::new(2, "random:1");
+        assert_eq!(play(&mut series, &[1, 1], 3), Some(1));
+    }
+
+    #[test]
+    fn most_wins_after_the_last_round() {
+        let mut series = Series::new(4, "random:1");
+        assert_eq!(play(&mut series, &[0, 0, 1, 2], 4), Some(0));
+    }
+
+    #[test]
+    fn last_round_breaks_a_tie() {
+        let mut series = Series::new(3, "random:1");
+        assert_eq!(play(&mut series, &[0, 1, 2], 3), Some(2));
+        let mut series = Series::new(3, "random:1");
+        assert_eq!(play(&mut series, &[0, 2, 2, 1, 1], 5), Some(1));
+        let mut series = Series::new(4, "random:1");
+        assert_eq!(play(&mut series, &[0, 1, 2, 3], 4), Some(3));
+    }
+}
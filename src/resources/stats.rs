@@ -0,0 +1,72 @@
+use crate::components::Combatant;
+use crate::resources::Teams;
+
+// published by PlayerDeathSystem
+#[derive(Clone, Debug)]
+pub struct PlayerKilled {
+    pub victim: Combatant,
+    pub killer: Option<Combatant>,
+    pub weapon: Option<usize>,
+}
+
+#[derive(Default, Clone, Debug)]
+pub struct PlayerStats {
+    pub kills: u32,
+    pub deaths: u32,
+    pub damage_dealt: f32,
+    // scoring ticks, seconds at the default scoring.interval
+    pub time_in_area: u32,
+    pub shots_hit: u32,
+}
+
+#[derive(Default)]
+pub struct Stats {
+    pub list: Vec<(Combatant, PlayerStats)>,
+}
+
+impl Stats {
+    pub fn entry(&mut self, combatant: Combatant) -> &mut PlayerStats {
+        let index = match self.list.iter().position(|(c, _)| *c == combatant) {
+            Some(index) => index,
+            None => {
+                self.list.push((combatant, PlayerStats::default()));
+                self.list.len() - 1
+            }
+        };
+        &mut self.list[index].1
+    }
+
+    pub fn table(&self, teams: &Teams) -> String {
+        let mut list: Vec<&(Combatant, PlayerStats)> = self.list.iter().collect();
+        // humans before the bots of their team
+        list.sort_by_key(|(c, _)| match c {
+            Combatant::Human { id, team } => (*team, 0, *id),
+            Combatant::Bot { team, slot } => (*team, 1, *slot as usize),
+        });
+        let mut table = format!(
+            "{:<12}{:>6}{:>6}{:>8}{:>6}{:>6}",
+            "", "KILL", "DEATH", "DAMAGE", "HIT", "AREA"
+        );
+        for (combatant, stats) in list {
+            table += &format!(
+                "\n{:<12}{:>6}{:>6}{:>8.0}{:>6}{:>6}",
+                name(*combatant, teams),
+                stats.kills,
+                stats.deaths,
+                stats.damage_dealt,
+                stats.shots_hit,
+                stats.time_in_area,
+            );
+        }
+        table
+    }
+}
+
+pub fn name(combatant: Combatant, teams: &Teams) -> String {
+    match combatant {
+        Combatant::Human { id, .. } => format!("P{}", id + 1),
+        Combatant::Bot { team, slot } => {
+            format!("{} BOT {}", teams.name(team).to_uppercase(), slot + 1)
+        }
+    }
+}
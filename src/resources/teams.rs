@@ -0,0 +1,79 @@
+use serde_derive::{Deserialize, Serialize};
+use std::ops::Deref;
+
+use crate::validation::{Validate, Validator};
+
+#[derive(Deserialize, Serialize, Default)]
+pub struct Teams {
+    pub list: Vec<Team>,
+    // the area while no team or several teams hold it
+    pub neutral_area_sprite: usize,
+}
+
+impl Deref for Teams {
+    type Target = Vec<Team>;
+
+    fn deref(&self) -> &Vec<Team> {
+        &self.list
+    }
+}
+
+impl Teams {
+    pub fn sprite_offset(&self, team: u32) -> usize {
+        self.list
+            .get(team as usize)
+            .map_or(0, |team| team.sprite_offset)
+    }
+
+    pub fn name(&self, team: u32) -> &str {
+        self.list
+            .get(team as usize)
+            .map_or("???", |team| team.name.as_str())
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct Team {
+    pub name: String,
+    pub sprite_offset: usize,
+    pub color: (f32, f32, f32, f32),
+    pub prefab: String,
+    pub area_sprite: usize,
+    // used when the map has no spawn point for this team
+    #[serde(default)]
+    pub spawn_points: Vec<(f32, f32)>,
+    // ui image shown when this team wins, a text label is used otherwise
+    #[serde(default)]
+    pub win_tag: Option<String>,
+}
+
+impl Validate for Teams {
+    fn validate(&self, v: &mut Validator) {
+        v.check(
+            self.list.len() >= 2,
+            "list: at least two teams are required",
+        );
+        for (i, team) in self.list.iter().enumerate() {
+            let field = |name: &str| format!("list[{}].{}", i, name);
+            v.check(
+                !team.name.is_empty(),
+                format!("{}: must not be empty", field("name")),
+            );
+            let (r, g, b, a) = team.color;
+            for (channel, value) in [("r", r), ("g", g), ("b", b), ("a", a)].iter() {
+                v.non_negative(&field(&format!("color.{}", channel)), *value);
+            }
+            v.sprite(&field("area_sprite"), team.area_sprite);
+            #[cfg(feature = "include_resources")]
+            v.check(
+                crate::resources::embedded_unit_prefab(&team.prefab).is_some(),
+                format!(
+                    "{}: {} is not embedded, add it to embedded_unit_prefab",
+                    field("prefab"),
+                    team.prefab
+                ),
+            );
+        }
+        v.sprite("neutral_area_sprite", self.neutral_area_sprite);
+    }
+}
@@ -1,6 +1,9 @@
 use serde_derive::{Deserialize, Serialize};
 use std::ops::Deref;
 
+use crate::components::{Explosion, Intercept, StatusEffect, StatusKind};
+use crate::validation::{Validate, Validator};
+
 #[derive(Deserialize, Serialize, Default)]
 pub struct WeaponList {
     pub list: Vec<Weapon>,
@@ -14,10 +17,84 @@ impl Deref for WeaponList {
     }
 }
 
+impl WeaponList {
+    pub fn weapon(&self, index: usize) -> &Weapon {
+        &self.list[index.min(self.list.len() - 1)]
+    }
+}
+
+impl Validate for WeaponList {
+    fn validate(&self, v: &mut Validator) {
+        v.check(
+            !self.list.is_empty(),
+            "list: at least one weapon is required",
+        );
+        for (i, weapon) in self.list.iter().enumerate() {
+            let field = |name: &str| format!("list[{}].{}", i, name);
+            v.non_negative(&field("move_speed"), weapon.move_speed);
+            v.non_negative(&field("bullet_damage"), weapon.bullet_damage);
+            v.team_sprite(&field("bullet_sprite"), weapon.bullet_sprite);
+            v.non_negative(&field("bullet_spread"), weapon.bullet_spread);
+            v.non_negative(&field("bullet_speed"), weapon.bullet_speed);
+            v.non_negative(&field("bullet_drag"), weapon.bullet_drag);
+            v.non_negative(&field("bullet_bounciness"), weapon.bullet_bounciness);
+            v.non_negative(&field("bullet_friction"), weapon.bullet_friction);
+            v.positive(&field("bullet_collider.0"), weapon.bullet_collider.0);
+            v.positive(&field("bullet_collider.1"), weapon.bullet_collider.1);
+            v.non_negative(&field("bullet_knockback"), weapon.bullet_knockback);
+            v.non_negative(&field("bullet_slowing"), weapon.bullet_slowing);
+            v.check(
+                weapon.bullet_slowing <= 1.0,
+                format!(
+                    "{}: must be at most 1.0 (got {})",
+                    field("bullet_slowing"),
+                    weapon.bullet_slowing
+                ),
+            );
+            match weapon.bullet_intercept {
+                // these lose hp for every bullet they stop
+                Intercept::Absorb | Intercept::Reflect => {
+                    v.positive(&field("bullet_hp"), weapon.bullet_hp)
+                }
+                _ => v.non_negative(&field("bullet_hp"), weapon.bullet_hp),
+            }
+            if let Some(explosion) = &weapon.bullet_explosion {
+                v.non_negative(&field("bullet_explosion.damage"), explosion.damage);
+                v.positive(&field("bullet_explosion.radius"), explosion.radius);
+                v.non_negative(&field("bullet_explosion.knockback"), explosion.knockback);
+            }
+            for (j, effect) in weapon.bullet_effects.iter().enumerate() {
+                let field = |name: &str| format!("list[{}].bullet_effects[{}].{}", i, j, name);
+                // what is given to the tofu that is hit, buffs and protection are not
+                match effect.kind {
+                    StatusKind::Slow(value)
+                    | StatusKind::DamageOverTime(value)
+                    | StatusKind::Weakened(value) => v.non_negative(&field("kind"), value),
+                    StatusKind::Stun => {}
+                    _ => v.check(
+                        false,
+                        format!(
+                            "{}: a bullet can only give Slow, DamageOverTime, Stun or Weakened",
+                            field("kind")
+                        ),
+                    ),
+                }
+                v.check(
+                    effect.duration > 0,
+                    format!("{}: must be greater than zero", field("duration")),
+                );
+            }
+        }
+    }
+}
+
 #[derive(Deserialize, Serialize)]
 pub struct Weapon {
+    #[serde(default)]
+    pub name: String,
     pub move_speed: f32,
     pub rate: u32,
+    pub shot_sound: usize,
     pub bullet_damage: f32,
     pub bullet_sprite: usize,
     pub bullet_spread: f32,
@@ -31,4 +108,12 @@ pub struct Weapon {
     pub bullet_knockback: f32,
     pub bullet_slowing: f32,
     pub bullet_pierce: bool,
+    #[serde(default)]
+    pub bullet_effects: Vec<StatusEffect>,
+    #[serde(default)]
+    pub bullet_hp: f32,
+    #[serde(default)]
+    pub bullet_intercept: Intercept,
+    #[serde(default)]
+    pub bullet_explosion: Option<Explosion>,
 }
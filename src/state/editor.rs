@@ -0,0 +1,705 @@
+use amethyst::{
+    assets::Handle,
+    core::{math::*, HiddenPropagate, Transform},
+    ecs::prelude::*,
+    input::{is_key_down, InputHandler, StringBindings},
+    prelude::*,
+    renderer::{palette::Srgba, resources::Tint, Camera, SpriteRender, SpriteSheet, Transparent},
+    ui::*,
+    window::ScreenDimensions,
+    winit::{MouseButton, VirtualKeyCode},
+};
+
+use crate::common::collision2d::{FIELD_HEIGHT, FIELD_WIDTH};
+use crate::common::pause::Pause;
+use crate::mapdata::{self, MapDocument, Object, Piece, TileRect, SNAP, TILE, WALL_SPRITE};
+use crate::prefab::MapPrefabData;
+use crate::resources::{Series, Teams};
+use crate::state::{clear_field, load_map_prefab, read_map};
+use crate::validation::{self, DataContext};
+
+// maps that were generated or made in Tiled are saved as a new RON file
+const NEW_MAP: &str = "resources/maps/edited.ron";
+// maps that lost something on the way in are saved next to the original
+const COPY_SUFFIX: &str = "_edited.ron";
+// the whole field is in view while editing
+const HALF_WIDTH: f32 = 400.0;
+const PAN_SPEED: f32 = 8.0;
+// steps that can be undone
+const HISTORY: usize = 100;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Tool {
+    Wall,
+    Spawn,
+    Area,
+    Target,
+    Flag,
+}
+
+impl Tool {
+    fn object(self, team: u32) -> Option<Object> {
+        match self {
+            Tool::Wall => None,
+            Tool::Spawn => Some(Object::Spawn(team)),
+            Tool::Area => Some(Object::Area),
+            Tool::Target => Some(Object::Target(team)),
+            Tool::Flag => Some(Object::Flag(team)),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Tool::Wall => "WALL",
+            Tool::Spawn => "SPAWN POINT",
+            Tool::Area => "AREA",
+            Tool::Target => "AREA TARGET",
+            Tool::Flag => "FLAG",
+        }
+    }
+}
+
+enum Drag {
+    // the wall stretches from the anchor tile to the one under the cursor
+    Resize {
+        wall: usize,
+        anchor: (usize, usize),
+    },
+    // the piece follows the cursor from where it was grabbed
+    Move {
+        piece: Piece,
+        grab: Vector2<f32>,
+        origin: Vector2<f32>,
+    },
+}
+
+// places, moves, resizes and deletes the walls and objects of the map on the title,
+// pushed with E
+pub struct Editor {
+    // where the map is saved
+    path: String,
+    document: MapDocument,
+    undo: Vec<MapDocument>,
+    redo: Vec<MapDocument>,
+    // the document before the mouse was pressed, undone as one step
+    before_drag: Option<MapDocument>,
+    drag: Option<Drag>,
+    tool: Tool,
+    team: u32,
+    // the document the view entities were made from
+    shown: Option<MapDocument>,
+    view: Vec<Entity>,
+    cursor: Option<Entity>,
+    // the map is loaded and the bots play on it
+    playtest: bool,
+    saved: bool,
+    // the last save wrote a map the title can load
+    playable: bool,
+    // Esc was pressed once with unsaved changes
+    leaving: bool,
+    message: String,
+    // left and right buttons in the last frame
+    mouse: (bool, bool),
+}
+
+impl Default for Editor {
+    fn default() -> Self {
+        Editor {
+            path: NEW_MAP.to_string(),
+            document: MapDocument::default(),
+            undo: Vec::new(),
+            redo: Vec::new(),
+            before_drag: None,
+            drag: None,
+            tool: Tool::Wall,
+            team: 0,
+            shown: None,
+            view: Vec::new(),
+            cursor: None,
+            playtest: false,
+            saved: true,
+            playable: false,
+            leaving: false,
+            message: String::new(),
+            mouse: (false, false),
+        }
+    }
+}
+
+impl Editor {
+    fn push_undo(&mut self, before: MapDocument) {
+        self.undo.push(before);
+        if self.undo.len() > HISTORY {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+        self.saved = false;
+        self.leaving = false;
+    }
+
+    fn undo(&mut self) {
+        if let Some(previous) = self.undo.pop() {
+            let current = std::mem::replace(&mut self.document, previous);
+            self.redo.push(current);
+            self.saved = false;
+        }
+    }
+
+    fn redo(&mut self) {
+        if let Some(next) = self.redo.pop() {
+            let current = std::mem::replace(&mut self.document, next);
+            self.undo.push(current);
+            self.saved = false;
+        }
+    }
+
+    fn press(&mut self, position: Vector2<f32>, resize: bool) {
+        self.before_drag = Some(self.document.clone());
+        let piece = self.document.piece_at(position.x, position.y);
+        self.drag = match piece {
+            Some(Piece::Wall(wall)) if resize => {
+                // the corner away from the cursor stays where it is
+                let rect = self.document.walls[wall].0;
+                let (x, y) = tile(position);
+                let anchor = (
+                    if x * 2 + 1 >= rect.x * 2 + rect.w {
+                        rect.x
+                    } else {
+                        rect.x + rect.w - 1
+                    },
+                    if y * 2 + 1 >= rect.y * 2 + rect.h {
+                        rect.y
+                    } else {
+                        rect.y + rect.h - 1
+                    },
+                );
+                Some(Drag::Resize { wall, anchor })
+            }
+            Some(piece) => Some(Drag::Move {
+                piece,
+                grab: position,
+                origin: self.origin(piece),
+            }),
+            None => self.place(position),
+        };
+    }
+
+    fn place(&mut self, position: Vector2<f32>) -> Option<Drag> {
+        let (x, y) = (
+            mapdata::snap(position.x, SNAP),
+            mapdata::snap(position.y, SNAP),
+        );
+        match self.tool.object(self.team) {
+            None => {
+                let (tile_x, tile_y) = tile(position);
+                let rect = TileRect {
+                    x: tile_x,
+                    y: tile_y,
+                    w: 1,
+                    h: 1,
+                };
+                self.document.walls.push((rect, WALL_SPRITE));
+                Some(Drag::Resize {
+                    wall: self.document.walls.len() - 1,
+                    anchor: (tile_x, tile_y),
+                })
+            }
+            // there is only one area, it is moved instead
+            Some(Object::Area) => {
+                let objects = &mut self.document.objects;
+                match objects.iter_mut().find(|object| object.0 == Object::Area) {
+                    Some(area) => *area = (Object::Area, x, y),
+                    None => objects.push((Object::Area, x, y)),
+                }
+                None
+            }
+            Some(object) => {
+                self.document.objects.push((object, x, y));
+                None
+            }
+        }
+    }
+
+    // the bottom left of a wall, the center of an object
+    fn origin(&self, piece: Piece) -> Vector2<f32> {
+        match piece {
+            Piece::Wall(i) => {
+                let rect = self.document.walls[i].0;
+                Vector2::new(rect.x as f32 * TILE, rect.y as f32 * TILE)
+            }
+            Piece::Object(i) => {
+                let (_, x, y) = self.document.objects[i];
+                Vector2::new(x, y)
+            }
+        }
+    }
+
+    fn drag_to(&mut self, position: Vector2<f32>) {
+        match self.drag {
+            Some(Drag::Resize { wall, anchor }) => {
+                let (x, y) = tile(position);
+                let rect = &mut self.document.walls[wall].0;
+                rect.x = x.min(anchor.0);
+                rect.y = y.min(anchor.1);
+                rect.w = x.max(anchor.0) - rect.x + 1;
+                rect.h = y.max(anchor.1) - rect.y + 1;
+            }
+            Some(Drag::Move {
+                piece,
+                grab,
+                origin,
+            }) => {
+                let moved = origin + position - grab;
+                match piece {
+                    Piece::Wall(i) => {
+                        let rect = &mut self.document.walls[i].0;
+                        rect.x = (moved.x / TILE).round().max(0.0) as usize;
+                        rect.y = (moved.y / TILE).round().max(0.0) as usize;
+                    }
+                    Piece::Object(i) => {
+                        let object = &mut self.document.objects[i];
+                        object.1 = mapdata::snap(moved.x, SNAP);
+                        object.2 = mapdata::snap(moved.y, SNAP);
+                    }
+                }
+            }
+            None => {}
+        }
+    }
+
+    fn release(&mut self) {
+        self.drag = None;
+        if let Some(before) = self.before_drag.take() {
+            if before != self.document {
+                self.push_undo(before);
+            }
+        }
+    }
+
+    fn delete(&mut self, position: Vector2<f32>) {
+        if let Some(piece) = self.document.piece_at(position.x, position.y) {
+            let before = self.document.clone();
+            self.document.remove(piece);
+            self.push_undo(before);
+        }
+    }
+
+    fn save(&mut self, world: &World) {
+        let prefab = self.document.to_prefab(&world.read_resource::<Teams>());
+        // a map with problems is saved all the same so the work is not lost,
+        // but to the copy, the map it was opened from stays playable
+        let context = world.read_resource::<DataContext>().clone();
+        let checked = validation::check(&prefab, &self.path, &context);
+        if let Err(e) = &checked {
+            log::error!("{}", e);
+        }
+        let path = if checked.is_err() {
+            copy_path(&self.path)
+        } else {
+            self.path.clone()
+        };
+        let written = mapdata::to_ron(&prefab)
+            .map_err(|e| e.to_string())
+            .and_then(|text| std::fs::write(&path, text).map_err(|e| e.to_string()));
+        self.message = match (written, checked) {
+            (Err(e), _) => format!("COULD NOT SAVE: {}", e),
+            (Ok(()), Err(_)) => {
+                self.saved = true;
+                self.playable = false;
+                format!("SAVED AS {}, SEE THE LOG FOR ITS PROBLEMS", path)
+            }
+            (Ok(()), Ok(())) => {
+                self.saved = true;
+                self.playable = true;
+                format!("SAVED {}", self.path)
+            }
+        };
+        self.leaving = false;
+    }
+
+    fn start_playtest(&mut self, world: &mut World) {
+        let prefab = self.document.to_prefab(&world.read_resource::<Teams>());
+        let context = world.read_resource::<DataContext>().clone();
+        if let Err(e) = validation::check(&prefab, &self.path, &context) {
+            log::error!("{}", e);
+            self.message = "THE MAP CANNOT BE PLAYED, SEE THE LOG".to_string();
+            return;
+        }
+        self.clear_view(world);
+        // the title spawns the bots once the spawn points are there
+        load_map_prefab(world, prefab);
+        world.write_resource::<Pause>().off();
+        self.playtest = true;
+        self.message.clear();
+    }
+
+    fn stop_playtest(&mut self, world: &mut World) {
+        clear_field(world);
+        world.write_resource::<Pause>().on();
+        self.playtest = false;
+        frame_camera(world, &self.document);
+    }
+
+    fn clear_view(&mut self, world: &mut World) {
+        for entity in self.view.drain(..) {
+            let _ = world.delete_entity(entity);
+        }
+        self.shown = None;
+    }
+
+    fn show(&mut self, world: &mut World) {
+        self.clear_view(world);
+        let view = self.document.view(&world.read_resource::<Teams>());
+        for data in view {
+            if let Some(entity) = create_sprite(world, data) {
+                self.view.push(entity);
+            }
+        }
+        self.shown = Some(self.document.clone());
+    }
+
+    // a faded copy of what the tool places, where it would go
+    fn show_cursor(&mut self, world: &mut World, position: Vector2<f32>) {
+        let data = match self.tool.object(self.team) {
+            None => {
+                let (x, y) = tile(position);
+                mapdata::wall(TileRect { x, y, w: 1, h: 1 }, WALL_SPRITE)
+            }
+            Some(object) => {
+                let preview = MapDocument {
+                    walls: Vec::new(),
+                    objects: vec![(
+                        object,
+                        mapdata::snap(position.x, SNAP),
+                        mapdata::snap(position.y, SNAP),
+                    )],
+                };
+                let mut view = preview.view(&world.read_resource::<Teams>());
+                view.remove(0)
+            }
+        };
+        if let Some(cursor) = self.cursor.take() {
+            let _ = world.delete_entity(cursor);
+        }
+        self.cursor = create_sprite(world, data);
+        if let Some(cursor) = self.cursor {
+            let tint = Tint(Srgba::new(1.0, 1.0, 1.0, 0.5));
+            let _ = world.write_storage::<Tint>().insert(cursor, tint);
+        }
+    }
+
+    fn text(&self) -> String {
+        if self.playtest {
+            return "PLAYTEST\nP  BACK TO EDITING".to_string();
+        }
+        let unsaved = if self.saved { "" } else { " *" };
+        format!(
+            "EDITOR  {}{}\n\
+             {} TEAM {}  (1-5 TOOL, T TEAM)\n\
+             LEFT PLACE / MOVE  SHIFT+LEFT RESIZE  RIGHT DELETE  ARROWS SCROLL\n\
+             CTRL+Z UNDO  CTRL+Y REDO  CTRL+S SAVE  P PLAYTEST  ESC BACK\n\
+             {}",
+            self.path,
+            unsaved,
+            self.tool.name(),
+            self.team,
+            self.message
+        )
+    }
+}
+
+impl SimpleState for Editor {
+    fn handle_event(
+        &mut self,
+        data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        let StateData { world, .. } = data;
+        let event = match &event {
+            StateEvent::Window(event) => event,
+            _ => return Trans::None,
+        };
+        let key = |key: VirtualKeyCode| is_key_down(event, key);
+
+        if key(VirtualKeyCode::Escape) {
+            if !self.playtest {
+                if self.saved || self.leaving {
+                    return Trans::Pop;
+                }
+                self.leaving = true;
+                self.message = "UNSAVED CHANGES, ESC AGAIN TO LEAVE WITHOUT SAVING".to_string();
+                return Trans::None;
+            }
+            self.stop_playtest(world);
+        } else if key(VirtualKeyCode::P) {
+            self.release();
+            if self.playtest {
+                self.stop_playtest(world);
+            } else {
+                self.start_playtest(world);
+            }
+        }
+        if self.playtest {
+            return Trans::None;
+        }
+
+        let (ctrl, shift) = {
+            let input = world.read_resource::<InputHandler<StringBindings>>();
+            let down = |key: VirtualKeyCode| input.key_is_down(key);
+            (
+                down(VirtualKeyCode::LControl) || down(VirtualKeyCode::RControl),
+                down(VirtualKeyCode::LShift) || down(VirtualKeyCode::RShift),
+            )
+        };
+        let tools = [
+            (VirtualKeyCode::Key1, Tool::Wall),
+            (VirtualKeyCode::Key2, Tool::Spawn),
+            (VirtualKeyCode::Key3, Tool::Area),
+            (VirtualKeyCode::Key4, Tool::Target),
+            (VirtualKeyCode::Key5, Tool::Flag),
+        ];
+        for &(code, tool) in tools.iter() {
+            if key(code) {
+                self.tool = tool;
+            }
+        }
+        if key(VirtualKeyCode::T) {
+            let team_count = world.read_resource::<Teams>().len().max(1) as u32;
+            self.team = (self.team + 1) % team_count;
+        }
+        if ctrl && (key(VirtualKeyCode::Z) || key(VirtualKeyCode::Y)) {
+            self.release();
+            if key(VirtualKeyCode::Y) || shift {
+                self.redo();
+            } else {
+                self.undo();
+            }
+        }
+        if ctrl && key(VirtualKeyCode::S) {
+            self.release();
+            self.save(world);
+        }
+        Trans::None
+    }
+
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let StateData { world, .. } = data;
+
+        world.write_resource::<Pause>().on();
+        clear_field(world);
+
+        // the map the title was showing
+        let map = world.read_resource::<Series>().map.clone();
+        let (document, warnings) = match read_map(world, &map) {
+            Ok(prefab) => MapDocument::from_prefab(&prefab),
+            Err(e) => {
+                log::error!("{}", e);
+                (
+                    MapDocument::default(),
+                    vec![format!("{} could not be read", map)],
+                )
+            }
+        };
+        for warning in warnings.iter() {
+            log::warn!("{}: {}", map, warning);
+        }
+        self.document = document;
+        let editable = map.ends_with(".ron") && mapdata::random_map_seed(&map).is_none();
+        if editable && warnings.is_empty() {
+            self.path = map;
+        } else if editable {
+            // saving over the map would lose what was left out
+            self.path = copy_path(&map);
+        }
+        if !warnings.is_empty() {
+            self.message = format!(
+                "{} THINGS WERE LEFT OUT, SEE THE LOG. THE MAP IS SAVED AS A COPY",
+                warnings.len()
+            );
+        }
+        frame_camera(world, &self.document);
+
+        world.exec(
+            |(finder, mut hidden): (UiFinder<'_>, WriteStorage<'_, HiddenPropagate>)| {
+                if let Some(entity) = finder.find("title") {
+                    let _ = hidden.insert(entity, HiddenPropagate::new());
+                }
+                if let Some(entity) = finder.find("editor_text") {
+                    hidden.remove(entity);
+                }
+            },
+        );
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let StateData { world, .. } = data;
+
+        self.clear_view(world);
+        if let Some(cursor) = self.cursor.take() {
+            let _ = world.delete_entity(cursor);
+        }
+        // the title loads the map again, the saved one if it can
+        clear_field(world);
+        if self.playable {
+            world.write_resource::<Series>().map = self.path.clone();
+        }
+        world.exec(
+            |(finder, mut hidden): (UiFinder<'_>, WriteStorage<'_, HiddenPropagate>)| {
+                if let Some(entity) = finder.find("editor_text") {
+                    let _ = hidden.insert(entity, HiddenPropagate::new());
+                }
+            },
+        );
+    }
+
+    fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+        let StateData { world, .. } = data;
+
+        if !self.playtest {
+            let (left, right, shift, pan) = {
+                let input = world.read_resource::<InputHandler<StringBindings>>();
+                let down = |key: VirtualKeyCode| if input.key_is_down(key) { 1.0 } else { 0.0 };
+                let pan = Vector2::new(
+                    down(VirtualKeyCode::Right) - down(VirtualKeyCode::Left),
+                    down(VirtualKeyCode::Up) - down(VirtualKeyCode::Down),
+                );
+                (
+                    input.mouse_button_is_down(MouseButton::Left),
+                    input.mouse_button_is_down(MouseButton::Right),
+                    input.key_is_down(VirtualKeyCode::LShift)
+                        || input.key_is_down(VirtualKeyCode::RShift),
+                    pan,
+                )
+            };
+            if pan != Vector2::zeros() {
+                world.exec(
+                    |(cameras, mut transforms): (
+                        ReadStorage<'_, Camera>,
+                        WriteStorage<'_, Transform>,
+                    )| {
+                        for (_, transform) in (&cameras, &mut transforms).join() {
+                            transform.prepend_translation_x(pan.x * PAN_SPEED);
+                            transform.prepend_translation_y(pan.y * PAN_SPEED);
+                        }
+                    },
+                );
+            }
+
+            if let Some(position) = mouse_position(world) {
+                if left && !self.mouse.0 {
+                    self.press(position, shift);
+                } else if left {
+                    self.drag_to(position);
+                }
+                if right && !self.mouse.1 && self.drag.is_none() {
+                    self.delete(position);
+                }
+                self.show_cursor(world, position);
+            }
+            if !left && self.mouse.0 {
+                self.release();
+            }
+            self.mouse = (left, right);
+
+            if self.shown.as_ref() != Some(&self.document) {
+                self.show(world);
+            }
+        } else if let Some(cursor) = self.cursor.take() {
+            let _ = world.delete_entity(cursor);
+        }
+
+        let text = self.text();
+        world.exec(
+            |(finder, mut texts): (UiFinder<'_>, WriteStorage<'_, UiText>)| {
+                if let Some(entity) = finder.find("editor_text") {
+                    if let Some(ui_text) = texts.get_mut(entity) {
+                        ui_text.text = text;
+                    }
+                }
+            },
+        );
+
+        Trans::None
+    }
+}
+
+// the tile under the point, the field starts at 0
+fn tile(position: Vector2<f32>) -> (usize, usize) {
+    (
+        (position.x / TILE).max(0.0) as usize,
+        (position.y / TILE).max(0.0) as usize,
+    )
+}
+
+fn create_sprite(world: &mut World, data: MapPrefabData) -> Option<Entity> {
+    let (transform, sprite) = match (data.transform, data.sprite) {
+        (Some(transform), Some(sprite)) => (transform, sprite),
+        _ => return None,
+    };
+    let sprite_sheet = world.read_resource::<Handle<SpriteSheet>>().clone();
+    let entity = world
+        .create_entity()
+        .with(transform)
+        .with(SpriteRender {
+            sprite_sheet,
+            sprite_number: sprite.sprite_number,
+        })
+        .with(Transparent)
+        .build();
+    Some(entity)
+}
+
+// the mouse in world coordinates, like PlayableSystem works it out
+fn mouse_position(world: &World) -> Option<Vector2<f32>> {
+    let input = world.read_resource::<InputHandler<StringBindings>>();
+    let screen = world.read_resource::<ScreenDimensions>();
+    let cameras = world.read_storage::<Camera>();
+    let transforms = world.read_storage::<Transform>();
+    let v = input.mouse_position()?;
+    let (camera, transform) = (&cameras, &transforms).join().next()?;
+    let ortho = camera.projection().as_orthographic()?;
+    Some(
+        transform.translation().xy()
+            + Vector2::new(
+                (v.0 as f32 / screen.width() - 0.5) * (ortho.right() - ortho.left()),
+                (v.1 as f32 / screen.height() - 0.5) * (ortho.bottom() - ortho.top()),
+            ),
+    )
+}
+
+// zoomed out over the middle of the walls
+fn frame_camera(world: &mut World, document: &MapDocument) {
+    let mut min = Vector2::new(0.0, 0.0);
+    let mut max = Vector2::new(FIELD_WIDTH, FIELD_HEIGHT);
+    for (rect, _) in document.walls.iter() {
+        min = min.inf(&Vector2::new(rect.x as f32 * TILE, rect.y as f32 * TILE));
+        max = max.sup(&Vector2::new(
+            (rect.x + rect.w) as f32 * TILE,
+            (rect.y + rect.h) as f32 * TILE,
+        ));
+    }
+    let center = (min + max) / 2.0;
+    world.exec(
+        |(mut cameras, mut transforms): (WriteStorage<'_, Camera>, WriteStorage<'_, Transform>)| {
+            for (camera, transform) in (&mut cameras, &mut transforms).join() {
+                transform.set_translation_x(center.x);
+                transform.set_translation_y(center.y);
+                if let Some(ortho) = camera.projection_mut().as_orthographic_mut() {
+                    let half_height = HALF_WIDTH * 3.0 / 4.0;
+                    ortho.set_left_and_right(-HALF_WIDTH, HALF_WIDTH);
+                    // Y axis reversed
+                    ortho.set_bottom_and_top(half_height, -half_height);
+                }
+            }
+        },
+    );
+}
+
+// where a map is saved when it must not replace the one it came from
+fn copy_path(path: &str) -> String {
+    if path.ends_with(COPY_SUFFIX) {
+        path.to_string()
+    } else {
+        format!("{}{}", path.trim_end_matches(".ron"), COPY_SUFFIX)
+    }
+}
@@ -1,5 +1,5 @@
 use amethyst::{
-    assets::{AssetStorage, Handle, Loader, PrefabLoader, RonFormat},
+    assets::{AssetStorage, Handle, Loader, Prefab, PrefabLoader, ProgressCounter, RonFormat},
     core::transform::*,
     core::{HiddenPropagate, Time},
     ecs::prelude::*,
@@ -13,16 +13,23 @@ use amethyst::{
 
 use crate::audio::*;
 use crate::components::*;
+use crate::mapdata;
 use crate::prefab::*;
 use crate::resources::*;
 use crate::state::*;
+use crate::validation::{self, DataContext, DataError, ValidationError};
 
 use crate::common::pause::Pause;
+use crate::common::ronfile;
 
 #[derive(Default)]
 pub struct Game {
     fps_display: Option<Entity>,
     released: bool,
+    // the pause menu was opened on the title, nothing to reset when it closes
+    in_menu: bool,
+    // the title shows the edited map again when the editor is closed
+    in_editor: bool,
 }
 
 impl SimpleState for Game {
@@ -32,7 +39,15 @@ impl SimpleState for Game {
         event: StateEvent,
     ) -> SimpleTrans {
         match &event {
-            StateEvent::Window(event) if is_key_down(&event, VirtualKeyCode::Escape) => Trans::Quit,
+            StateEvent::Window(event) if is_key_down(&event, VirtualKeyCode::Escape) => {
+                self.in_menu = true;
+                Trans::Push(Box::new(PauseMenu::new(PauseOrigin::Title)))
+            }
+            // the editor clears the field, the title starts over when it is closed
+            StateEvent::Window(event) if is_key_down(&event, VirtualKeyCode::E) => {
+                self.in_editor = true;
+                Trans::Push(Box::new(Editor::default()))
+            }
             _ => Trans::None,
         }
     }
@@ -62,11 +77,26 @@ impl SimpleState for Game {
         let StateData { world, .. } = data;
 
         // spawn npc
-        if world.read_resource::<Time>().frame_number() % 128 == 0 {
+        let spawn_interval = world
+            .read_resource::<MatchRules>()
+            .bots
+            .spawn_interval
+            .max(1);
+        if world.read_resource::<Time>().frame_number() % spawn_interval == 0 {
             let mut rh = (*world.read_resource::<RespawnHandler>()).clone();
             rh.respawn_npc(world);
             *world.write_resource::<RespawnHandler>() = rh;
         }
+
+        // hot reload balance data
+        #[cfg(not(feature = "include_resources"))]
+        {
+            if world.read_resource::<Time>().frame_number() % 30 == 0 {
+                let mut hot_reload = (*world.read_resource::<HotReload>()).clone();
+                hot_reload.update(world);
+                *world.write_resource::<HotReload>() = hot_reload;
+            }
+        }
     }
 
     fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
@@ -106,60 +136,26 @@ impl SimpleState for Game {
         let StateData { world, .. } = data;
 
         self.released = false;
-
-        world.write_resource::<Pause>().off();
-
-        macro_rules! skip_fail {
-            ($res:expr) => {
-                match $res {
-                    Ok(val) => val,
-                    Err(e) => {
-                        log::warn!("{} (L{})", e, line!());
-                        continue;
-                    }
-                }
-            };
+        if self.in_menu {
+            self.in_menu = false;
+            return;
         }
 
-        // delete entities
-        world.exec(
-            |(entities, players, hierarchy, bullets, items, particles): (
-                Entities,
-                ReadStorage<'_, Player>,
-                WriteExpect<'_, ParentHierarchy>,
-                ReadStorage<'_, Bullet>,
-                ReadStorage<'_, Item>,
-                ReadStorage<'_, Particle>,
-            )| {
-                for (entity, _) in (&entities, &players).join() {
-                    skip_fail!(entities.delete(entity));
-                    for entity in hierarchy.all_children_iter(entity) {
-                        skip_fail!(entities.delete(entity));
-                    }
-                }
-                for (entity, _) in (&entities, &bullets).join() {
-                    skip_fail!(entities.delete(entity));
-                }
-                for (entity, _) in (&entities, &items).join() {
-                    skip_fail!(entities.delete(entity));
-                }
-                for (entity, _) in (&entities, &particles).join() {
-                    skip_fail!(entities.delete(entity));
-                }
-            },
-        );
-
-        // reset area
-        world.exec(
-            |(areas, mut transforms): (ReadStorage<'_, Area>, WriteStorage<'_, Transform>)| {
-                for (_, transform) in (&areas, &mut transforms).join() {
-                    transform.set_translation_x(352.0);
-                }
-            },
-        );
+        world.write_resource::<Pause>().off();
 
-        // reset score
-        world.insert(Score { score: vec![0, 0] });
+        // a new series on the next map, or on the map the editor left
+        let map = {
+            let map_list = world.read_resource::<MapList>();
+            let mut series = world.write_resource::<Series>();
+            series.restart();
+            if self.in_editor {
+                self.in_editor = false;
+                series.map.clone()
+            } else {
+                series.next_map(&map_list).to_string()
+            }
+        };
+        reset_match(world, &map);
 
         // show title
         world.exec(
@@ -179,25 +175,96 @@ impl SimpleState for Game {
         let sprite_sheet_handle = load_sprite_sheet(world);
         world.insert(sprite_sheet_handle);
 
+        let sprite_count = validation::sprite_count().unwrap_or_else(|e| panic!("{}", e));
+        let mut context = DataContext::new(sprite_count);
+
+        #[cfg(feature = "include_resources")]
+        let teams = validation::load_bytes::<Teams>(
+            include_bytes!("../../resources/teams.ron"),
+            "resources/teams.ron",
+            &context,
+        );
+        #[cfg(not(feature = "include_resources"))]
+        let teams = validation::load::<Teams>("resources/teams.ron", &context);
+        let teams = teams.unwrap_or_else(|e| panic!("{}", e));
+        context.set_teams(&teams);
+        let team_count = teams.len();
+        world.insert(teams);
+
         #[cfg(feature = "include_resources")]
-        let weapon_list =
-            WeaponList::load_bytes(include_bytes!("../../resources/weapon_list.ron")).unwrap();
+        let local_players = validation::load_bytes::<LocalPlayers>(
+            include_bytes!("../../resources/local_players.ron"),
+            "resources/local_players.ron",
+            &context,
+        );
+        #[cfg(not(feature = "include_resources"))]
+        let local_players =
+            validation::load::<LocalPlayers>("resources/local_players.ron", &context);
+        world.insert(local_players.unwrap_or_else(|e| panic!("{}", e)));
+
+        #[cfg(feature = "include_resources")]
+        let weapon_list = validation::load_bytes::<WeaponList>(
+            include_bytes!("../../resources/weapon_list.ron"),
+            "resources/weapon_list.ron",
+            &context,
+        );
         #[cfg(not(feature = "include_resources"))]
-        let weapon_list = WeaponList::load("resources/weapon_list.ron").unwrap();
+        let weapon_list = validation::load::<WeaponList>("resources/weapon_list.ron", &context);
+        let weapon_list = weapon_list.unwrap_or_else(|e| panic!("{}", e));
+        context.weapon_count = weapon_list.len();
         world.insert(weapon_list);
 
-        world.insert(Score { score: vec![0, 0] });
+        #[cfg(feature = "include_resources")]
+        let item_list = validation::load_bytes::<ItemList>(
+            include_bytes!("../../resources/item_list.ron"),
+            "resources/item_list.ron",
+            &context,
+        );
+        #[cfg(not(feature = "include_resources"))]
+        let item_list = validation::load::<ItemList>("resources/item_list.ron", &context);
+        world.insert(item_list.unwrap_or_else(|e| panic!("{}", e)));
 
-        let prefab_handle = world.exec(|loader: PrefabLoader<'_, MapPrefabData>| {
-            #[cfg(feature = "include_resources")]
-            return loader.load_from_data(
-                Config::load_bytes(include_bytes!("../../resources/map.ron")).unwrap(),
-                (),
-            );
-            #[cfg(not(feature = "include_resources"))]
-            return loader.load("resources/map.ron", RonFormat, ());
-        });
-        world.create_entity().with(prefab_handle).build();
+        #[cfg(feature = "include_resources")]
+        let match_rules = validation::load_bytes::<MatchRules>(
+            include_bytes!("../../resources/match_rules.ron"),
+            "resources/match_rules.ron",
+            &context,
+        );
+        #[cfg(not(feature = "include_resources"))]
+        let match_rules = validation::load::<MatchRules>("resources/match_rules.ron", &context);
+        let match_rules = match_rules.unwrap_or_else(|e| panic!("{}", e));
+        world.insert(MatchClock::new(&match_rules.clock));
+        world.insert(match_rules);
+
+        world.insert(Score::new(team_count));
+        world.insert(Stats::default());
+        world.insert(GameMode::default());
+
+        #[cfg(feature = "include_resources")]
+        let map_list = validation::load_bytes::<MapList>(
+            include_bytes!("../../resources/maps/maps.ron"),
+            "resources/maps/maps.ron",
+            &context,
+        );
+        #[cfg(not(feature = "include_resources"))]
+        let map_list = validation::load::<MapList>("resources/maps/maps.ron", &context);
+        world.insert(map_list.unwrap_or_else(|e| panic!("{}", e)));
+
+        #[cfg(feature = "include_resources")]
+        let series_rules = validation::load_bytes::<SeriesRules>(
+            include_bytes!("../../resources/series.ron"),
+            "resources/series.ron",
+            &context,
+        );
+        #[cfg(not(feature = "include_resources"))]
+        let series_rules = validation::load::<SeriesRules>("resources/series.ron", &context);
+        world.insert(series_rules.unwrap_or_else(|e| panic!("{}", e)));
+        // maps are checked against it whenever they are loaded
+        world.insert(context.clone());
+        check_maps(world, &world.read_resource::<MapList>()).unwrap_or_else(|e| panic!("{}", e));
+        let map = mapdata::resolve_map(world.read_resource::<MapList>().first());
+        world.insert(Series::new(team_count, &map));
+        load_map(world, &map);
 
         let prefab_handle = world.exec(|loader: PrefabLoader<'_, MyPrefabData>| {
             #[cfg(feature = "include_resources")]
@@ -227,13 +294,177 @@ impl SimpleState for Game {
         let ui_handle = world.exec(|loader: UiLoader<'_>| loader.load("resources/ui.ron", ()));
         world.create_entity().with(ui_handle).build();
 
-        let respawn_handler = RespawnHandler::initialize(world);
+        let respawn_handler =
+            RespawnHandler::initialize(world, &context).unwrap_or_else(|e| panic!("{}", e));
         world.insert(respawn_handler);
 
+        #[cfg(not(feature = "include_resources"))]
+        let hot_reload = HotReload::new(world, context);
+        #[cfg(not(feature = "include_resources"))]
+        world.insert(hot_reload);
+
         initialise_audio(world);
     }
 }
 
+// clears everything a match leaves behind and loads the map of the next one,
+// the map entities appear once the returned progress is complete
+pub fn reset_match(world: &mut World, map: &str) -> ProgressCounter {
+    clear_field(world);
+    let progress = load_map(world, map);
+
+    // reset score
+    let team_count = world.read_resource::<Teams>().len();
+    world.insert(Score::new(team_count));
+    world.insert(Stats::default());
+    let clock = MatchClock::new(&world.read_resource::<MatchRules>().clock);
+    world.insert(clock);
+
+    progress
+}
+
+// deletes the players, what they left around and the map
+pub fn clear_field(world: &mut World) {
+    macro_rules! skip_fail {
+        ($res:expr) => {
+            match $res {
+                Ok(val) => val,
+                Err(e) => {
+                    log::warn!("{} (L{})", e, line!());
+                    continue;
+                }
+            }
+        };
+    }
+
+    // delete entities
+    world.exec(
+        |(entities, players, hierarchy, bullets, items, particles, maps): (
+            Entities,
+            ReadStorage<'_, Player>,
+            WriteExpect<'_, ParentHierarchy>,
+            ReadStorage<'_, Bullet>,
+            ReadStorage<'_, Item>,
+            ReadStorage<'_, Particle>,
+            ReadStorage<'_, Map>,
+        )| {
+            for (entity, _) in (&entities, &players).join() {
+                skip_fail!(entities.delete(entity));
+                for entity in hierarchy.all_children_iter(entity) {
+                    skip_fail!(entities.delete(entity));
+                }
+            }
+            for (entity, _) in (&entities, &bullets).join() {
+                skip_fail!(entities.delete(entity));
+            }
+            for (entity, _) in (&entities, &items).join() {
+                skip_fail!(entities.delete(entity));
+            }
+            for (entity, _) in (&entities, &particles).join() {
+                skip_fail!(entities.delete(entity));
+            }
+            for (entity, _) in (&entities, &maps).join() {
+                skip_fail!(entities.delete(entity));
+            }
+        },
+    );
+}
+
+// a map that cannot be read or has problems is logged and the last good one is played again
+fn load_map(world: &mut World, path: &str) -> ProgressCounter {
+    let context = world.read_resource::<DataContext>().clone();
+    let checked = read_map(world, path).and_then(|prefab| {
+        validation::check(&prefab, path, &context)?;
+        Ok(prefab)
+    });
+    match checked {
+        Ok(prefab) => {
+            world.write_resource::<Series>().last_valid = path.to_string();
+            load_map_prefab(world, prefab)
+        }
+        Err(e) => {
+            let fallback = world.read_resource::<Series>().last_valid.clone();
+            log::error!("{}", e);
+            log::warn!("playing {} instead of {}", fallback, path);
+            world.write_resource::<Series>().map = fallback.clone();
+            // it loaded before, and the maps listed were all checked when the game started
+            let prefab = read_map(world, &fallback).unwrap_or_else(|e| panic!("{}", e));
+            load_map_prefab(world, prefab)
+        }
+    }
+}
+
+// the prefab of a map file, or of a generated map
+pub fn read_map(world: &World, path: &str) -> Result<Prefab<MapPrefabData>, DataError> {
+    if let Some(seed) = mapdata::random_map_seed(path) {
+        Ok(mapdata::generate(seed).to_prefab(&world.read_resource::<Teams>()))
+    } else if mapdata::is_tiled(path) {
+        import_tiled(world, path)
+    } else {
+        #[cfg(feature = "include_resources")]
+        let prefab = ronfile::load_bytes(map_bytes(path)?, path)?;
+        #[cfg(not(feature = "include_resources"))]
+        let prefab = ronfile::load(path)?;
+        Ok(prefab)
+    }
+}
+
+// reads and checks every map that can come up, so a broken one stops the game at startup
+fn check_maps(world: &World, map_list: &MapList) -> Result<(), DataError> {
+    let context = world.read_resource::<DataContext>().clone();
+    for map in map_list
+        .maps
+        .iter()
+        .filter(|map| map.path != mapdata::RANDOM_MAP)
+    {
+        validation::check(&read_map(world, &map.path)?, &map.path, &context)?;
+    }
+    Ok(())
+}
+
+// starts loading a map that passed validation::check
+pub fn load_map_prefab(world: &mut World, prefab: Prefab<MapPrefabData>) -> ProgressCounter {
+    let mut progress = ProgressCounter::new();
+    let prefab_handle = world.exec(|loader: PrefabLoader<'_, MapPrefabData>| {
+        loader.load_from_data(prefab, &mut progress)
+    });
+    // tagged so the root goes with the rest of the map
+    world.create_entity().with(prefab_handle).with(Map).build();
+    progress
+}
+
+fn map_error(path: &str, problem: impl ToString) -> ValidationError {
+    ValidationError {
+        source: path.to_string(),
+        problems: vec![problem.to_string()],
+    }
+}
+
+// maps made in Tiled are converted when they are loaded
+fn import_tiled(world: &World, path: &str) -> Result<Prefab<MapPrefabData>, DataError> {
+    #[cfg(feature = "include_resources")]
+    let text = String::from_utf8_lossy(map_bytes(path)?).into_owned();
+    #[cfg(not(feature = "include_resources"))]
+    let text = std::fs::read_to_string(path).map_err(|e| map_error(path, e))?;
+    let teams = world.read_resource::<Teams>();
+    let (prefab, warnings) =
+        mapdata::import_tiled(&text, path, &teams).map_err(|e| map_error(path, e))?;
+    for warning in warnings {
+        log::warn!("{}: {}", path, warning);
+    }
+    Ok(prefab)
+}
+
+#[cfg(feature = "include_resources")]
+fn map_bytes(path: &str) -> Result<&'static [u8], ValidationError> {
+    match path {
+        "resources/maps/classic.ron" => Ok(include_bytes!("../../resources/maps/classic.ron")),
+        "resources/maps/winding.ron" => Ok(include_bytes!("../../resources/maps/winding.ron")),
+        "resources/maps/sample.tmx" => Ok(include_bytes!("../../resources/maps/sample.tmx")),
+        _ => Err(map_error(path, "is not embedded, add it to map_bytes")),
+    }
+}
+
 fn load_sprite_sheet(world: &mut World) -> Handle<SpriteSheet> {
     let texture_handle = {
         let loader = world.read_resource::<Loader>();
@@ -8,12 +8,15 @@ use amethyst::{
 };
 
 use crate::common::pause::Pause;
+use crate::resources::{Series, SeriesRules, Stats, Teams};
+use crate::state::{Intermission, PauseMenu, PauseOrigin};
 
 #[derive(Default)]
 pub struct GameOver {
     pub win: usize,
     pub released: bool,
     pub timer: u64,
+    series_over: bool,
 }
 
 impl SimpleState for GameOver {
@@ -23,7 +26,9 @@ impl SimpleState for GameOver {
         event: StateEvent,
     ) -> SimpleTrans {
         match &event {
-            StateEvent::Window(event) if is_key_down(&event, VirtualKeyCode::Escape) => Trans::Quit,
+            StateEvent::Window(event) if is_key_down(&event, VirtualKeyCode::Escape) => {
+                Trans::Push(Box::new(PauseMenu::new(PauseOrigin::Results)))
+            }
             _ => Trans::None,
         }
     }
@@ -33,10 +38,55 @@ impl SimpleState for GameOver {
 
         world.write_resource::<Pause>().on();
 
+        let (round, best_of) = {
+            let best_of = world.read_resource::<SeriesRules>().best_of;
+            let mut series = world.write_resource::<Series>();
+            series.win(self.win);
+            self.series_over = series.winner(best_of).is_some();
+            (series.round, best_of)
+        };
+
+        let (win_tag, win_text, table) = {
+            let teams = world.read_resource::<Teams>();
+            let team = &teams[self.win];
+            let table = world.read_resource::<Stats>().table(&teams);
+            let name = team.name.to_uppercase();
+            let win_text = if !self.series_over {
+                format!("{} TAKES ROUND {}", name, round)
+            } else if best_of > 1 {
+                format!("{} WINS THE SERIES", name)
+            } else {
+                format!("{} WINS", name)
+            };
+            // the team's own picture is kept for the end of the series
+            let win_tag = team.win_tag.clone().filter(|_| self.series_over);
+            (win_tag, win_text, table)
+        };
         world.exec(
-            |(finder, mut hidden): (UiFinder<'_>, WriteStorage<'_, HiddenPropagate>)| {
-                let tag = if self.win == 0 { "bluewin" } else { "redwin" };
-                if let Some(entity) = finder.find(tag) {
+            |(finder, mut hidden, mut texts): (
+                UiFinder<'_>,
+                WriteStorage<'_, HiddenPropagate>,
+                WriteStorage<'_, UiText>,
+            )| {
+                match win_tag {
+                    Some(tag) => {
+                        if let Some(entity) = finder.find(&tag) {
+                            hidden.remove(entity);
+                        }
+                    }
+                    None => {
+                        if let Some(entity) = finder.find("win_text") {
+                            if let Some(text) = texts.get_mut(entity) {
+                                text.text = win_text;
+                            }
+                            hidden.remove(entity);
+                        }
+                    }
+                }
+                if let Some(entity) = finder.find("stats_text") {
+                    if let Some(text) = texts.get_mut(entity) {
+                        text.text = table;
+                    }
                     hidden.remove(entity);
                 }
             },
@@ -57,23 +107,10 @@ impl SimpleState for GameOver {
         };
 
         if pressed_any_key && self.released {
-            // hide gameover
-            world.exec(
-                |(finder, mut hidden): (UiFinder<'_>, WriteStorage<'_, HiddenPropagate>)| {
-                    if let Some(entity) = finder.find("bluewin") {
-                        if hidden.insert(entity, HiddenPropagate::new()).is_err() {
-                            log::warn!("Failed to insert HiddenPropagate component");
-                        }
-                    }
-                    if let Some(entity) = finder.find("redwin") {
-                        if hidden.insert(entity, HiddenPropagate::new()).is_err() {
-                            log::warn!("Failed to insert HiddenPropagate component");
-                        }
-                    }
-                },
-            );
-
-            return Trans::Pop;
+            if self.series_over {
+                return Trans::Pop;
+            }
+            return Trans::Switch(Box::new(Intermission::default()));
         }
 
         if !pressed_any_key {
@@ -82,4 +119,29 @@ impl SimpleState for GameOver {
 
         Trans::None
     }
+
+    // also left from the pause menu
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let StateData { world, .. } = data;
+
+        // hide gameover
+        let mut tags: Vec<String> = world
+            .read_resource::<Teams>()
+            .iter()
+            .filter_map(|team| team.win_tag.clone())
+            .collect();
+        tags.push("win_text".to_string());
+        tags.push("stats_text".to_string());
+        world.exec(
+            |(finder, mut hidden): (UiFinder<'_>, WriteStorage<'_, HiddenPropagate>)| {
+                for tag in tags.iter() {
+                    if let Some(entity) = finder.find(tag) {
+                        if hidden.insert(entity, HiddenPropagate::new()).is_err() {
+                            log::warn!("Failed to insert HiddenPropagate component");
+                        }
+                    }
+                }
+            },
+        );
+    }
 }
@@ -0,0 +1,94 @@
+use amethyst::{
+    assets::ProgressCounter, core::HiddenPropagate, ecs::prelude::*, input::is_key_down,
+    prelude::*, ui::*, winit::VirtualKeyCode,
+};
+
+use crate::common::pause::Pause;
+use crate::resources::{GameMode, MapList, Series, SeriesRules};
+use crate::state::{map_modes, reset_match, PauseMenu, PauseOrigin};
+
+// between two rounds of a series, loads the next map while the round number is shown
+#[derive(Default)]
+pub struct Intermission {
+    timer: u32,
+    progress: Option<ProgressCounter>,
+}
+
+impl SimpleState for Intermission {
+    fn handle_event(
+        &mut self,
+        _data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        match &event {
+            StateEvent::Window(event) if is_key_down(&event, VirtualKeyCode::Escape) => {
+                Trans::Push(Box::new(PauseMenu::new(PauseOrigin::Results)))
+            }
+            _ => Trans::None,
+        }
+    }
+
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let StateData { world, .. } = data;
+
+        let (round, map) = {
+            let map_list = world.read_resource::<MapList>();
+            let mut series = world.write_resource::<Series>();
+            series.round += 1;
+            (series.round, series.next_map(&map_list).to_string())
+        };
+        self.progress = Some(reset_match(world, &map));
+
+        world.exec(
+            |(finder, mut hidden, mut texts): (
+                UiFinder<'_>,
+                WriteStorage<'_, HiddenPropagate>,
+                WriteStorage<'_, UiText>,
+            )| {
+                if let Some(entity) = finder.find("win_text") {
+                    if let Some(text) = texts.get_mut(entity) {
+                        text.text = format!("ROUND {}", round);
+                    }
+                    hidden.remove(entity);
+                }
+            },
+        );
+    }
+
+    fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+        let StateData { world, .. } = data;
+
+        self.timer += 1;
+
+        let loaded = self
+            .progress
+            .as_ref()
+            .map_or(true, |progress| progress.is_complete());
+        if !loaded || self.timer < world.read_resource::<SeriesRules>().intermission {
+            return Trans::None;
+        }
+
+        // keep the mode if the new map supports it
+        let modes = map_modes(world);
+        let mode = *world.read_resource::<GameMode>();
+        if !modes.contains(&mode) {
+            *world.write_resource::<GameMode>() = modes[0];
+        }
+
+        world.write_resource::<Pause>().off();
+
+        Trans::Pop
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let StateData { world, .. } = data;
+
+        world.exec(
+            |(finder, mut hidden): (UiFinder<'_>, WriteStorage<'_, HiddenPropagate>)| {
+                if let Some(entity) = finder.find("win_text") {
+                    let _ = hidden.insert(entity, HiddenPropagate::new());
+                }
+            },
+        );
+    }
+}
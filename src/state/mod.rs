@@ -2,8 +2,14 @@ pub mod game;
 pub mod select;
 pub mod playing;
 pub mod gameover;
+pub mod intermission;
+pub mod pausemenu;
+pub mod editor;
 
 pub use game::*;
 pub use select::*;
 pub use playing::*;
 pub use gameover::*;
+pub use intermission::*;
+pub use pausemenu::*;
+pub use editor::*;
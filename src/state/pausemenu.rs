@@ -0,0 +1,307 @@
+use amethyst::{
+    audio::AudioSink,
+    core::{transform::ParentHierarchy, HiddenPropagate},
+    ecs::prelude::*,
+    input::{is_key_down, InputHandler, StringBindings},
+    prelude::*,
+    ui::*,
+    winit::VirtualKeyCode,
+};
+
+use crate::audio::AudioSettings;
+use crate::components::*;
+use crate::resources::*;
+use crate::state::{reset_match, Select};
+
+use crate::common::pause::Pause;
+
+// the state the menu was opened from
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PauseOrigin {
+    Title,
+    Select,
+    Playing,
+    // the results of a round or the intermission after them
+    Results,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum MenuItem {
+    Resume,
+    Restart,
+    ChangeWeapon,
+    Settings,
+    QuitToTitle,
+    Quit,
+    MusicVolume,
+    SoundVolume,
+    Back,
+}
+
+pub struct PauseMenu {
+    from: PauseOrigin,
+    in_settings: bool,
+    selecting: usize,
+    released: bool,
+    timer: i32,
+    was_paused: bool,
+}
+
+impl PauseMenu {
+    pub fn new(from: PauseOrigin) -> Self {
+        PauseMenu {
+            from,
+            in_settings: false,
+            selecting: 0,
+            released: false,
+            timer: 0,
+            was_paused: false,
+        }
+    }
+
+    fn items(&self) -> Vec<MenuItem> {
+        if self.in_settings {
+            return vec![MenuItem::MusicVolume, MenuItem::SoundVolume, MenuItem::Back];
+        }
+        match self.from {
+            PauseOrigin::Title => vec![MenuItem::Resume, MenuItem::Settings, MenuItem::Quit],
+            PauseOrigin::Select | PauseOrigin::Results => vec![
+                MenuItem::Resume,
+                MenuItem::Restart,
+                MenuItem::Settings,
+                MenuItem::QuitToTitle,
+                MenuItem::Quit,
+            ],
+            PauseOrigin::Playing => vec![
+                MenuItem::Resume,
+                MenuItem::Restart,
+                MenuItem::ChangeWeapon,
+                MenuItem::Settings,
+                MenuItem::QuitToTitle,
+                MenuItem::Quit,
+            ],
+        }
+    }
+
+    // pops back to Select, or to the title with one more
+    fn pop_to_select(&self, extra: usize) -> SimpleTrans {
+        Trans::Sequence(self.pops_to_select(extra))
+    }
+
+    fn pops_to_select(&self, extra: usize) -> Vec<SimpleTrans> {
+        let count = match self.from {
+            PauseOrigin::Playing | PauseOrigin::Results => 2,
+            _ => 1,
+        } + extra;
+        (0..count).map(|_| Trans::Pop).collect()
+    }
+
+    fn activate(&mut self, world: &mut World, item: MenuItem) -> SimpleTrans {
+        match item {
+            MenuItem::Resume => Trans::Pop,
+            MenuItem::Restart => {
+                // a new series on the same map, chosen again by a Select that waits for it
+                let map = {
+                    let mut series = world.write_resource::<Series>();
+                    series.restart();
+                    series.map.clone()
+                };
+                let progress = reset_match(world, &map);
+                // the results leave the game paused, the new match is not
+                self.was_paused = false;
+                let mut pops = self.pops_to_select(0);
+                pops.push(Trans::Switch(Box::new(Select::restart(progress))));
+                Trans::Sequence(pops)
+            }
+            MenuItem::ChangeWeapon => {
+                // the humans leave the field without counting as deaths
+                world.exec(
+                    |(entities, playables, hierarchy): (
+                        Entities<'_>,
+                        ReadStorage<'_, Playable>,
+                        ReadExpect<'_, ParentHierarchy>,
+                    )| {
+                        for (entity, _) in (&entities, &playables).join() {
+                            let _ = entities.delete(entity);
+                            for child in hierarchy.all_children_iter(entity) {
+                                let _ = entities.delete(child);
+                            }
+                        }
+                    },
+                );
+                self.pop_to_select(0)
+            }
+            MenuItem::Settings | MenuItem::Back => {
+                self.in_settings = !self.in_settings;
+                self.selecting = 0;
+                Trans::None
+            }
+            MenuItem::QuitToTitle => self.pop_to_select(1),
+            MenuItem::Quit => Trans::Quit,
+            MenuItem::MusicVolume | MenuItem::SoundVolume => Trans::None,
+        }
+    }
+}
+
+impl SimpleState for PauseMenu {
+    fn handle_event(
+        &mut self,
+        _data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        match &event {
+            StateEvent::Window(event) if is_key_down(&event, VirtualKeyCode::Escape) => {
+                if self.in_settings {
+                    self.in_settings = false;
+                    self.selecting = 0;
+                    Trans::None
+                } else {
+                    Trans::Pop
+                }
+            }
+            _ => Trans::None,
+        }
+    }
+
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let StateData { world, .. } = data;
+
+        let mut pause = world.write_resource::<Pause>();
+        self.was_paused = pause.paused();
+        pause.on();
+        if let Some(sink) = world.try_fetch::<AudioSink>() {
+            sink.pause();
+        }
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let StateData { world, .. } = data;
+
+        if !self.was_paused {
+            world.write_resource::<Pause>().off();
+        }
+        if let Some(sink) = world.try_fetch::<AudioSink>() {
+            sink.play();
+        }
+        world.exec(
+            |(finder, mut hidden): (UiFinder<'_>, WriteStorage<'_, HiddenPropagate>)| {
+                if let Some(entity) = finder.find("pause_text") {
+                    let _ = hidden.insert(entity, HiddenPropagate::new());
+                }
+            },
+        );
+    }
+
+    fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+        let StateData { world, .. } = data;
+
+        let (x, y, shot) = {
+            let input = world.read_resource::<InputHandler<StringBindings>>();
+            let local_players = world.read_resource::<LocalPlayers>();
+            let mut x = 0.0;
+            let mut y = 0.0;
+            let mut shot = false;
+            for local in local_players.iter() {
+                let move_vec = local.move_input(&input);
+                x += move_vec.x;
+                y += move_vec.y;
+                shot |= local.shot(&input);
+            }
+            (x, y, shot)
+        };
+
+        let items = self.items();
+        if self.timer > 0 {
+            self.timer -= 1;
+        }
+        if self.timer < 0 {
+            self.timer += 1;
+        }
+        if y < -0.1 && self.timer >= 0 {
+            self.selecting = (self.selecting + items.len() - 1) % items.len();
+            self.timer = -10;
+        }
+        if y > 0.1 && self.timer <= 0 {
+            self.selecting = (self.selecting + 1) % items.len();
+            self.timer = 10;
+        }
+        self.selecting %= items.len();
+        let item = items[self.selecting];
+
+        // left and right change the volumes
+        let step = if x < -0.1 {
+            -0.1
+        } else if x > 0.1 {
+            0.1
+        } else {
+            0.0
+        };
+        let volume_item = item == MenuItem::MusicVolume || item == MenuItem::SoundVolume;
+        if step != 0.0 && volume_item && self.timer == 0 {
+            let mut settings = world.write_resource::<AudioSettings>();
+            let volume = if item == MenuItem::MusicVolume {
+                &mut settings.music
+            } else {
+                &mut settings.sound
+            };
+            *volume = (*volume + step).max(0.0).min(1.0);
+            if let Some(mut sink) = world.try_fetch_mut::<AudioSink>() {
+                sink.set_volume(settings.music);
+            }
+            self.timer = 10;
+        }
+
+        let mut trans = Trans::None;
+        if shot && self.released {
+            self.released = false;
+            trans = self.activate(world, item);
+        }
+        if !shot {
+            self.released = true;
+        }
+
+        let text = menu_text(self, world);
+        world.exec(
+            |(finder, mut hidden, mut texts): (
+                UiFinder<'_>,
+                WriteStorage<'_, HiddenPropagate>,
+                WriteStorage<'_, UiText>,
+            )| {
+                if let Some(entity) = finder.find("pause_text") {
+                    hidden.remove(entity);
+                    if let Some(ui_text) = texts.get_mut(entity) {
+                        ui_text.text = text;
+                    }
+                }
+            },
+        );
+
+        trans
+    }
+}
+
+fn menu_text(menu: &PauseMenu, world: &World) -> String {
+    let settings = *world.read_resource::<AudioSettings>();
+    let mut text = if menu.in_settings {
+        "SETTINGS".to_string()
+    } else {
+        "PAUSED".to_string()
+    };
+    text += "\n";
+    for (i, item) in menu.items().into_iter().enumerate() {
+        let label = match item {
+            MenuItem::Resume => "RESUME".to_string(),
+            MenuItem::Restart => "RESTART MATCH".to_string(),
+            MenuItem::ChangeWeapon => "CHANGE WEAPON".to_string(),
+            MenuItem::Settings => "SETTINGS".to_string(),
+            MenuItem::QuitToTitle => "QUIT TO TITLE".to_string(),
+            MenuItem::Quit => "QUIT".to_string(),
+            MenuItem::MusicVolume => format!("MUSIC  < {:.0}% >", settings.music * 100.0),
+            MenuItem::SoundVolume => format!("SOUND  < {:.0}% >", settings.sound * 100.0),
+            MenuItem::Back => "BACK".to_string(),
+        };
+        let cursor = if i == menu.selecting { "> " } else { "  " };
+        text += &format!("\n{}{}", cursor, label);
+    }
+    text
+}
@@ -1,9 +1,4 @@
-use amethyst::{
-    ecs::prelude::*,
-    input::is_key_down,
-    prelude::*,
-    winit::VirtualKeyCode,
-};
+use amethyst::{ecs::prelude::*, input::is_key_down, prelude::*, winit::VirtualKeyCode};
 
 use crate::components::*;
 use crate::resources::*;
@@ -12,6 +7,7 @@ use crate::state::*;
 #[derive(Default)]
 pub struct Playing {
     timer: Option<i32>,
+    respawn_timers: Vec<Option<i32>>,
 }
 
 impl SimpleState for Playing {
@@ -21,7 +17,9 @@ impl SimpleState for Playing {
         event: StateEvent,
     ) -> SimpleTrans {
         match &event {
-            StateEvent::Window(event) if is_key_down(&event, VirtualKeyCode::Escape) => Trans::Quit,
+            StateEvent::Window(event) if is_key_down(&event, VirtualKeyCode::Escape) => {
+                Trans::Push(Box::new(PauseMenu::new(PauseOrigin::Playing)))
+            }
             _ => Trans::None,
         }
     }
@@ -30,23 +28,51 @@ impl SimpleState for Playing {
         let StateData { world, .. } = data;
 
         // check gameover
-        let score = world.read_resource::<Score>();
-        let position = score.score[0] as i32 - score.score[1] as i32;
-        let ratio = position as f32 / 100.0 + 0.5;
-        if ratio <= 0.0 {
-            return Trans::Switch(Box::new(GameOver {
-                win: 1,
-                ..Default::default()
-            }));
+        {
+            let score = world.read_resource::<Score>();
+            let rules = world.read_resource::<MatchRules>();
+            let clock_winner = world.read_resource::<MatchClock>().winner;
+            let held = AreaPath::held_on(&world.read_storage::<AreaPath>());
+            let mode_winner = world
+                .read_resource::<GameMode>()
+                .winner(&score, &rules, held);
+            if let Some(win) = mode_winner.or(clock_winner) {
+                return Trans::Switch(Box::new(GameOver {
+                    win,
+                    ..Default::default()
+                }));
+            }
         }
-        if ratio >= 1.0 {
-            return Trans::Switch(Box::new(GameOver {
-                win: 0,
-                ..Default::default()
-            }));
+
+        let local_count = world.read_resource::<LocalPlayers>().len();
+        let mut alive = vec![false; local_count];
+        for playable in world.read_storage::<Playable>().join() {
+            if let Some(alive) = alive.get_mut(playable.id) {
+                *alive = true;
+            }
+        }
+
+        // humans who die while others are still playing respawn with the same weapon,
+        // once everyone is dead they all go back to Select
+        self.respawn_timers.resize(local_count, None);
+        if alive.contains(&true) {
+            for (id, timer) in self.respawn_timers.iter_mut().enumerate() {
+                if alive[id] {
+                    *timer = None;
+                    continue;
+                }
+                let count = timer.get_or_insert(180);
+                *count -= 1;
+                if *count < 0 {
+                    *timer = None;
+                    let mut rh = (*world.read_resource::<RespawnHandler>()).clone();
+                    rh.respawn_player(world, id);
+                    *world.write_resource::<RespawnHandler>() = rh;
+                }
+            }
         }
 
-        if self.timer.is_none() && world.read_storage::<Playable>().join().next().is_none() {
+        if self.timer.is_none() && !alive.contains(&true) {
             self.timer = Some(60);
         }
 
@@ -1,13 +1,17 @@
 use amethyst::{
+    assets::{Handle, ProgressCounter},
     core::HiddenPropagate,
     ecs::prelude::*,
     input::{is_key_down, InputHandler, StringBindings},
     prelude::*,
+    renderer::{SpriteRender, SpriteSheet},
     ui::*,
     window::ScreenDimensions,
     winit::VirtualKeyCode,
 };
 
+use crate::components::*;
+use crate::mapdata;
 use crate::resources::*;
 use crate::state::*;
 
@@ -15,11 +19,96 @@ use crate::common::pause::Pause;
 
 #[derive(Default)]
 pub struct Select {
+    players: Vec<PlayerSelect>,
+    map: Choice,
+    map_preview: Option<Entity>,
+    // the chosen map while it loads
+    loading: Option<ProgressCounter>,
+    mode: Choice,
+}
+
+// map and game mode choices made by any local player before the weapons
+#[derive(Default)]
+struct Choice {
+    chosen: bool,
+    selecting: usize,
+    released: bool,
+    timer: i32,
+}
+
+impl Choice {
+    // left and right go through the count options, shot picks one
+    fn update(&mut self, world: &World, count: usize) {
+        let (x, shot) = {
+            let input = world.read_resource::<InputHandler<StringBindings>>();
+            let local_players = world.read_resource::<LocalPlayers>();
+            let mut x = 0.0;
+            let mut shot = false;
+            for local in local_players.iter() {
+                x += local.move_input(&input).x;
+                shot |= local.shot(&input);
+            }
+            (x, shot)
+        };
+
+        if self.timer > 0 {
+            self.timer -= 1;
+        }
+        if self.timer < 0 {
+            self.timer += 1;
+        }
+        if x < -0.1 && self.timer >= 0 {
+            self.selecting = (self.selecting + count - 1) % count;
+            self.timer = -10;
+        }
+        if x > 0.1 && self.timer <= 0 {
+            self.selecting = (self.selecting + 1) % count;
+            self.timer = 10;
+        }
+        self.selecting %= count;
+        if shot && self.released {
+            self.chosen = true;
+        }
+        if !shot {
+            self.released = true;
+        }
+    }
+}
+
+// selection state of one local player
+#[derive(Default, Clone)]
+struct PlayerSelect {
     selecting: u32,
+    ready: bool,
     released: bool,
     timer: i32,
 }
 
+impl Select {
+    // the menu follows the first player still choosing
+    fn shown_weapon(&self) -> u32 {
+        self.players
+            .iter()
+            .find(|select| !select.ready)
+            .or_else(|| self.players.first())
+            .map_or(0, |select| select.selecting)
+    }
+
+    // the map and mode stay as they were, the map is loaded again
+    pub fn restart(loading: ProgressCounter) -> Self {
+        let chosen = || Choice {
+            chosen: true,
+            ..Default::default()
+        };
+        Select {
+            map: chosen(),
+            loading: Some(loading),
+            mode: chosen(),
+            ..Default::default()
+        }
+    }
+}
+
 impl SimpleState for Select {
     fn handle_event(
         &mut self,
@@ -27,7 +116,9 @@ impl SimpleState for Select {
         event: StateEvent,
     ) -> SimpleTrans {
         match &event {
-            StateEvent::Window(event) if is_key_down(&event, VirtualKeyCode::Escape) => Trans::Quit,
+            StateEvent::Window(event) if is_key_down(&event, VirtualKeyCode::Escape) => {
+                Trans::Push(Box::new(PauseMenu::new(PauseOrigin::Select)))
+            }
             _ => Trans::None,
         }
     }
@@ -38,140 +129,408 @@ impl SimpleState for Select {
         if world.read_resource::<Pause>().paused() {
             return Trans::Pop;
         }
+        let weapon_count = world.read_resource::<WeaponList>().len() as u32;
+
         // hide menu
-        world.exec(
-            |(finder, mut hidden): (UiFinder<'_>, WriteStorage<'_, HiddenPropagate>)| {
-                if let Some(entity) = finder.find("menu1") {
-                    let _ = hidden.insert(entity, HiddenPropagate::new());
-                }
-                if let Some(entity) = finder.find("menu2") {
-                    let _ = hidden.insert(entity, HiddenPropagate::new());
-                }
-                if let Some(entity) = finder.find("menu3") {
-                    let _ = hidden.insert(entity, HiddenPropagate::new());
-                }
-            },
-        );
+        hide_menu(world);
+
+        if !self.map.chosen {
+            self.choose_map(world);
+            return Trans::None;
+        }
+        let loaded = self
+            .loading
+            .as_ref()
+            .map_or(true, |progress| progress.is_complete());
+        if !loaded {
+            return Trans::None;
+        }
+        if !self.mode.chosen {
+            self.choose_mode(world);
+            return Trans::None;
+        }
 
         // check gameover
         {
             let score = world.read_resource::<Score>();
-            let position = score.score[0] as i32 - score.score[1] as i32;
-            let ratio = position as f32 / 100.0 + 0.5;
-            if ratio <= 0.0 {
+            let rules = world.read_resource::<MatchRules>();
+            let clock_winner = world.read_resource::<MatchClock>().winner;
+            let held = AreaPath::held_on(&world.read_storage::<AreaPath>());
+            let mode_winner = world
+                .read_resource::<GameMode>()
+                .winner(&score, &rules, held);
+            if let Some(win) = mode_winner.or(clock_winner) {
                 return Trans::Push(Box::new(GameOver {
-                    win: 1,
-                    ..Default::default()
-                }));
-            }
-            if ratio >= 1.0 {
-                return Trans::Push(Box::new(GameOver {
-                    win: 0,
+                    win,
                     ..Default::default()
                 }));
             }
         }
 
-        if self.timer > 0 {
-            self.timer -= 1;
-        }
-        if self.timer < 0 {
-            self.timer += 1;
-        }
+        let local_players = (*world.read_resource::<LocalPlayers>()).clone();
+        self.players
+            .resize(local_players.len(), PlayerSelect::default());
+        let first = first_row(self.shown_weapon() % weapon_count);
 
-        let (up, down, shot, mouse_vec) = {
+        {
             let input = world.read_resource::<InputHandler<StringBindings>>();
             let screen = world.read_resource::<ScreenDimensions>();
-
-            let move_y = input.axis_value("move_y").unwrap_or(0.0);
-            let dpad_y = input.axis_value("dpad_y").unwrap_or(0.0);
-            let left_y = input.axis_value("left_y").unwrap_or(0.0);
-            let y = move_y + dpad_y + left_y;
-            let up = y < -0.1;
-            let down = y > 0.1;
-            let shot = input.action_is_down("shot").unwrap_or(false);
             let mouse_vec = input.mouse_position().map(|v| {
                 (
                     v.0 as f32 / screen.width() * 1280.0,
                     v.1 as f32 / screen.height() * 960.0,
                 )
             });
-            (up, down, shot, mouse_vec)
-        };
 
-        if up && self.timer >= 0 {
-            self.selecting = (self.selecting + 2) % 3;
-            self.timer = -10;
-        }
-        if down && self.timer <= 0 {
-            self.selecting = (self.selecting + 1) % 3;
-            self.timer = 10;
-        }
-        if let Some((x, y)) = mouse_vec {
-            if x >= 50.0 && x < 520.0 {
-                if y >= 510.0 && y < 620.0 {
-                    self.selecting = 0;
+            for (select, local) in self.players.iter_mut().zip(local_players.iter()) {
+                let y = local.move_input(&input).y;
+                let up = y < -0.1;
+                let down = y > 0.1;
+                let shot = local.shot(&input);
+
+                if select.timer > 0 {
+                    select.timer -= 1;
                 }
-                if y >= 630.0 && y < 740.0 {
-                    self.selecting = 1;
+                if select.timer < 0 {
+                    select.timer += 1;
                 }
-                if y >= 750.0 && y < 860.0 {
-                    self.selecting = 2;
+
+                // hot reload may have shrunk the weapon list
+                select.selecting %= weapon_count;
+
+                if select.ready {
+                    continue;
+                }
+                if up && select.timer >= 0 {
+                    select.selecting = (select.selecting + weapon_count - 1) % weapon_count;
+                    select.timer = -10;
                 }
+                if down && select.timer <= 0 {
+                    select.selecting = (select.selecting + 1) % weapon_count;
+                    select.timer = 10;
+                }
+                if let (Some((x, y)), true) = (mouse_vec, local.uses_mouse()) {
+                    if x >= 50.0 && x < 520.0 {
+                        for row in 0..MENU_ROWS.min(weapon_count - first) {
+                            let top = 510.0 + row as f32 * 120.0;
+                            if y >= top && y < top + 110.0 {
+                                select.selecting = first + row;
+                            }
+                        }
+                    }
+                }
+
+                if shot && select.released {
+                    select.ready = true;
+                }
+                if !shot {
+                    select.released = true;
+                }
+            }
+        }
+
+        let shown = self.shown_weapon();
+        show_menu(world, shown);
+        if self.players.len() > 1 {
+            show_cursors(world, &self.players, first_row(shown));
+        }
+
+        if self.players.iter().all(|select| select.ready) {
+            // spawn players
+            for (id, select) in self.players.iter().enumerate() {
+                world.write_resource::<LocalPlayers>()[id].weapon = select.selecting as usize;
+                let mut rh = (*world.read_resource::<RespawnHandler>()).clone();
+                rh.respawn_player(world, id);
+                *world.write_resource::<RespawnHandler>() = rh;
             }
+
+            world.write_resource::<MatchClock>().running = true;
+
+            // hide menu
+            hide_menu(world);
+
+            return Trans::Push(Box::new(Playing::default()));
         }
 
+        Trans::None
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let StateData { world, .. } = data;
+
+        // left from the pause menu while choosing
+        hide_menu(world);
         world.exec(
             |(finder, mut hidden): (UiFinder<'_>, WriteStorage<'_, HiddenPropagate>)| {
-                if self.selecting == 0 {
-                    if let Some(entity) = finder.find("menu1") {
-                        hidden.remove(entity);
+                for id in ["map_text", "mode_text"].iter() {
+                    if let Some(entity) = finder.find(id) {
+                        let _ = hidden.insert(entity, HiddenPropagate::new());
                     }
                 }
-                if self.selecting == 1 {
-                    if let Some(entity) = finder.find("menu2") {
+            },
+        );
+        if let Some(preview) = self.map_preview.take() {
+            let _ = world.delete_entity(preview);
+        }
+    }
+
+    fn on_resume(&mut self, _data: StateData<'_, GameData<'_, '_>>) {
+        self.map.released = false;
+        self.mode.released = false;
+        for select in self.players.iter_mut() {
+            select.ready = false;
+            select.released = false;
+        }
+    }
+}
+
+impl Select {
+    fn choose_map(&mut self, world: &mut World) {
+        let maps = world.read_resource::<MapList>().maps.clone();
+        if maps.len() < 2 {
+            self.map.chosen = true;
+            return;
+        }
+
+        // starts on the map the series was going to be played on
+        if self.map_preview.is_none() {
+            let current = world.read_resource::<Series>().map.clone();
+            let position = world.read_resource::<MapList>().position(&current);
+            self.map.selecting = position.unwrap_or(0);
+            self.map_preview = Some(create_preview(world));
+        }
+        self.map.update(world, maps.len());
+
+        let map = &maps[self.map.selecting];
+        let chosen = self.map.chosen;
+        let modes: Vec<&str> = map.modes.iter().map(|mode| mode.name()).collect();
+        let mut label = format!("< {} >\n{}", map.name, modes.join(" / "));
+        if let Some(team_size) = map.team_size {
+            label += &format!("\n{} VS {}", team_size, team_size);
+        }
+        let preview = self.map_preview;
+        let sheet = world.read_resource::<Handle<SpriteSheet>>().clone();
+        world.exec(
+            |(finder, mut hidden, mut texts, mut images): (
+                UiFinder<'_>,
+                WriteStorage<'_, HiddenPropagate>,
+                WriteStorage<'_, UiText>,
+                WriteStorage<'_, UiImage>,
+            )| {
+                if let Some(entity) = finder.find("map_text") {
+                    if chosen {
+                        let _ = hidden.insert(entity, HiddenPropagate::new());
+                    } else {
                         hidden.remove(entity);
                     }
-                }
-                if self.selecting == 2 {
-                    if let Some(entity) = finder.find("menu3") {
-                        hidden.remove(entity);
+                    if let Some(text) = texts.get_mut(entity) {
+                        text.text = label;
                     }
                 }
+                if let Some(entity) = preview {
+                    let _ = images.insert(
+                        entity,
+                        UiImage::Sprite(SpriteRender {
+                            sprite_sheet: sheet,
+                            sprite_number: map.preview,
+                        }),
+                    );
+                }
             },
         );
+        if !chosen {
+            return;
+        }
 
-        if shot && self.released {
-            // spawn player
-            let mut rh = (*world.read_resource::<RespawnHandler>()).clone();
-            rh.respawn_player(world, self.selecting as usize);
-            *world.write_resource::<RespawnHandler>() = rh;
+        if let Some(preview) = self.map_preview.take() {
+            let _ = world.delete_entity(preview);
+        }
+        // the map the series started with goes away with everything on it,
+        // a random map is generated again
+        let path = mapdata::resolve_map(&map.path);
+        let changed = {
+            let mut series = world.write_resource::<Series>();
+            let changed = series.map != path;
+            series.map = path.clone();
+            changed
+        };
+        if changed {
+            self.loading = Some(reset_match(world, &path));
+        }
+    }
 
-            // hide menu
-            world.exec(
-                |(finder, mut hidden): (UiFinder<'_>, WriteStorage<'_, HiddenPropagate>)| {
-                    if let Some(entity) = finder.find("menu1") {
-                        let _ = hidden.insert(entity, HiddenPropagate::new());
-                    }
-                    if let Some(entity) = finder.find("menu2") {
+    fn choose_mode(&mut self, world: &mut World) {
+        let modes = map_modes(world);
+        if modes.len() == 1 {
+            self.mode.chosen = true;
+            *world.write_resource::<GameMode>() = modes[0];
+            return;
+        }
+
+        self.mode.update(world, modes.len());
+
+        let chosen = self.mode.chosen;
+        let label = format!("< {} >", modes[self.mode.selecting].name());
+        world.exec(
+            |(finder, mut hidden, mut texts): (
+                UiFinder<'_>,
+                WriteStorage<'_, HiddenPropagate>,
+                WriteStorage<'_, UiText>,
+            )| {
+                if let Some(entity) = finder.find("mode_text") {
+                    if chosen {
                         let _ = hidden.insert(entity, HiddenPropagate::new());
+                    } else {
+                        hidden.remove(entity);
                     }
-                    if let Some(entity) = finder.find("menu3") {
-                        let _ = hidden.insert(entity, HiddenPropagate::new());
+                    if let Some(text) = texts.get_mut(entity) {
+                        text.text = label;
                     }
-                },
-            );
-
-            return Trans::Push(Box::new(Playing::default()));
-        }
-        if !shot {
-            self.released = true;
+                }
+            },
+        );
+        if chosen {
+            *world.write_resource::<GameMode>() = modes[self.mode.selecting];
         }
-
-        Trans::None
     }
+}
 
-    fn on_resume(&mut self, _data: StateData<'_, GameData<'_, '_>>) {
-        self.released = false;
+fn create_preview(world: &mut World) -> Entity {
+    let transform = UiTransform::new(
+        "map_preview".to_string(),
+        Anchor::Middle,
+        Anchor::Middle,
+        0.0,
+        120.0,
+        1.0,
+        // map previews are 44x26 sprites
+        176.0,
+        104.0,
+    );
+    world.create_entity().with(transform).build()
+}
+
+// the modes of the map being played, from the map list or else the map itself,
+// Area if neither gives any
+pub fn map_modes(world: &World) -> Vec<GameMode> {
+    let listed = {
+        let series = world.read_resource::<Series>();
+        let map_list = world.read_resource::<MapList>();
+        map_list
+            .find(&series.map)
+            .map_or(Vec::new(), |map| map.modes.clone())
+    };
+    if !listed.is_empty() {
+        return listed;
+    }
+    let modes = world
+        .read_storage::<MapInfo>()
+        .join()
+        .next()
+        .map_or(Vec::new(), |info| info.modes.clone());
+    if modes.is_empty() {
+        vec![GameMode::Area]
+    } else {
+        modes
     }
 }
+
+// the weapon menu shows this many weapons at a time, a page after another
+const MENU_ROWS: u32 = 3;
+
+// the weapon at the top of the page the weapon is on
+fn first_row(weapon: u32) -> u32 {
+    weapon / MENU_ROWS * MENU_ROWS
+}
+
+fn row_id(row: u32) -> String {
+    format!("weapon_row{}", row + 1)
+}
+
+// the names of the weapons on the page of the shown one, which is marked
+fn show_menu(world: &mut World, shown: u32) {
+    let first = first_row(shown);
+    let rows: Vec<String> = {
+        let weapon_list = world.read_resource::<WeaponList>();
+        (first..first + MENU_ROWS)
+            .map(|i| match weapon_list.get(i as usize) {
+                Some(weapon) if i == shown => format!("> {}", weapon.name.to_uppercase()),
+                Some(weapon) => format!("  {}", weapon.name.to_uppercase()),
+                None => String::new(),
+            })
+            .collect()
+    };
+    world.exec(
+        |(finder, mut hidden, mut texts): (
+            UiFinder<'_>,
+            WriteStorage<'_, HiddenPropagate>,
+            WriteStorage<'_, UiText>,
+        )| {
+            if let Some(entity) = finder.find("weapon_menu") {
+                hidden.remove(entity);
+            }
+            for (row, text) in rows.into_iter().enumerate() {
+                if let Some(ui_text) = finder
+                    .find(&row_id(row as u32))
+                    .and_then(|entity| texts.get_mut(entity))
+                {
+                    ui_text.text = text;
+                }
+            }
+        },
+    );
+}
+
+fn hide_menu(world: &mut World) {
+    world.exec(
+        |(finder, mut hidden): (UiFinder<'_>, WriteStorage<'_, HiddenPropagate>)| {
+            if let Some(entity) = finder.find("weapon_menu") {
+                let _ = hidden.insert(entity, HiddenPropagate::new());
+            }
+            for id in 0..MAX_LOCAL_PLAYERS {
+                if let Some(entity) = finder.find(&cursor_id(id)) {
+                    let _ = hidden.insert(entity, HiddenPropagate::new());
+                }
+            }
+        },
+    );
+}
+
+fn cursor_id(id: usize) -> String {
+    format!("cursor{}", id + 1)
+}
+
+// marks the row each local player has chosen, if it is on the page shown
+fn show_cursors(world: &mut World, players: &[PlayerSelect], first: u32) {
+    world.exec(
+        |(finder, mut hidden, mut transforms, mut texts): (
+            UiFinder<'_>,
+            WriteStorage<'_, HiddenPropagate>,
+            WriteStorage<'_, UiTransform>,
+            WriteStorage<'_, UiText>,
+        )| {
+            for (id, select) in players.iter().enumerate() {
+                let entity = match finder.find(&cursor_id(id)) {
+                    Some(entity) => entity,
+                    None => continue,
+                };
+                if select.selecting < first || select.selecting >= first + MENU_ROWS {
+                    continue;
+                }
+                hidden.remove(entity);
+                let row = select.selecting - first;
+                if let Some(transform) = transforms.get_mut(entity) {
+                    transform.local_x = (600.0 + id as f32 * 100.0) / 1280.0;
+                    transform.local_y = -(565.0 + row as f32 * 120.0) / 960.0;
+                }
+                if let Some(text) = texts.get_mut(entity) {
+                    text.text = if select.ready {
+                        format!("P{} OK", id + 1)
+                    } else {
+                        format!("P{}", id + 1)
+                    };
+                }
+            }
+        },
+    );
+}
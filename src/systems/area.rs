@@ -1,7 +1,7 @@
 use amethyst::{core::math::*, core::Transform, ecs::prelude::*, renderer::SpriteRender};
 
 use crate::components::*;
-use crate::resources::Score;
+use crate::resources::{area_leader, GameMode, MatchRules, Score, Stats, Teams};
 use crate::skip_fail;
 
 #[derive(Default)]
@@ -10,18 +10,40 @@ pub struct AreaSystem {
 }
 impl<'s> System<'s> for AreaSystem {
     type SystemData = (
+        Entities<'s>,
         ReadStorage<'s, Player>,
         ReadStorage<'s, Area>,
         ReadStorage<'s, AreaTarget>,
+        WriteStorage<'s, AreaPath>,
         ReadStorage<'s, ColliderResult>,
         WriteStorage<'s, Transform>,
         WriteStorage<'s, SpriteRender>,
         WriteExpect<'s, Score>,
+        ReadExpect<'s, Teams>,
+        ReadStorage<'s, Playable>,
+        WriteExpect<'s, Stats>,
+        ReadExpect<'s, MatchRules>,
+        ReadExpect<'s, GameMode>,
     );
 
     fn run(
         &mut self,
-        (players, areas, targets, results, mut transforms, mut sprites, mut score): Self::SystemData,
+        (
+            entities,
+            players,
+            areas,
+            targets,
+            mut paths,
+            results,
+            mut transforms,
+            mut sprites,
+            mut score,
+            teams,
+            playables,
+            mut stats,
+            rules,
+            mode,
+        ): Self::SystemData,
     ) {
         self.timer += 1;
 
@@ -31,41 +53,74 @@ impl<'s> System<'s> for AreaSystem {
             }
         }
 
-        if self.timer % 60 != 0 {
+        let scoring = &rules.scoring;
+        if !mode.uses_area() {
+            // nothing is contested without an area, the score display would say otherwise
+            score.contested = false;
+            return;
+        }
+        if self.timer % scoring.interval.max(1) as i32 != 0 {
             return;
         }
 
-        for (_, result, transform, sprite) in
-            (&areas, &results, &mut transforms, &mut sprites).join()
+        // maps without a path push the area straight from the first team's target to the second's
+        let target = |team: u32| {
+            (&targets, &transforms)
+                .join()
+                .find(|(target, _)| target.team == team)
+                .map(|(_, transform)| (transform.translation().x, transform.translation().y))
+        };
+        if let (Some(from), Some(to)) = (target(0), target(1)) {
+            let pathless: Vec<Entity> = (&entities, &areas, !&paths)
+                .join()
+                .map(|(entity, _, _)| entity)
+                .collect();
+            for entity in pathless {
+                skip_fail!(paths.insert(entity, AreaPath::new(vec![from, to])));
+            }
+        }
+
+        for (_, result, transform, sprite, path) in (
+            &areas,
+            &results,
+            &mut transforms,
+            &mut sprites,
+            (&mut paths).maybe(),
+        )
+            .join()
         {
-            let mut p = 0i32;
+            let mut counts = vec![0u32; score.score.len()];
+            let mut counted = Vec::new();
             for collided in &result.collided {
                 let player = skip_fail!(players
                     .get(collided.entity)
                     .ok_or("Failed to get player component"));
-                let team = player.team;
-                score.score[team as usize] += 1;
-                match team {
-                    0 => p += 1,
-                    1 => p -= 1,
-                    _ => (),
-                };
+                if let Some(count) = counts.get_mut(player.team as usize) {
+                    *count += 1;
+                }
+                if !counted.contains(&collided.entity) {
+                    counted.push(collided.entity);
+                    let combatant = Combatant::of(player, playables.get(collided.entity));
+                    stats.entry(combatant).time_in_area += 1;
+                }
             }
-            let position = score.score[0] as i32 - score.score[1] as i32;
-            let ratio = position as f32 / 100.0 + 0.5;
-            let ratio = if ratio < 0.0 {
-                0.0
-            } else if ratio > 1.0 {
-                1.0
-            } else {
-                ratio
-            };
-            let position_x = 352.0 * ratio + 176.0;
-            transform.set_translation_x(position_x);
-            sprite.sprite_number = match p.cmp(&0) {
-                std::cmp::Ordering::Equal => 16,
-                std::cmp::Ordering::Less => 18,
-                std::cmp::Ordering::Greater => 17,
+
+            sprite.sprite_number = area_leader(&counts)
+                .and_then(|team| teams.get(team))
+                .map_or(teams.neutral_area_sprite, |team| team.area_sprite);
+            mode.score_area(&mut score, &counts, scoring.contested);
+
+            // the area is pushed along its path only in a two team match
+            if let (Some(path), 2, GameMode::Area) = (path, score.score.len(), *mode) {
+                let ratio = scoring.track_ratio(score.lead(0) + path.held);
+                let locked = path.lock(ratio);
+                // the score stays as earned, the path keeps what the checkpoint holds
+                if locked != ratio {
+                    path.held = scoring.track_lead(locked) - score.lead(0);
+                }
+                let position = path.position(locked);
+                transform.set_translation_x(position.x);
+                transform.set_translation_y(position.y);
             }
         }
     }
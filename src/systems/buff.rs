@@ -0,0 +1,68 @@
+use amethyst::{
+    core::transform::*,
+    core::{Hidden, Time},
+    ecs::prelude::*,
+    renderer::SpriteRender,
+};
+
+use crate::components::*;
+use crate::resources::ItemList;
+use crate::skip_fail;
+
+// frames each buff is shown for when the tofu has several
+const CYCLE: u64 = 40;
+// the icon blinks when the buff is about to run out
+const ENDING: u32 = 90;
+
+pub struct BuffIconSystem;
+impl<'s> System<'s> for BuffIconSystem {
+    type SystemData = (
+        Entities<'s>,
+        ReadStorage<'s, BuffIcon>,
+        WriteStorage<'s, SpriteRender>,
+        ReadStorage<'s, Parent>,
+        ReadStorage<'s, StatusEffects>,
+        WriteStorage<'s, Hidden>,
+        ReadExpect<'s, ItemList>,
+        Read<'s, Time>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            icons,
+            mut renders,
+            parents,
+            status_effects,
+            mut hiddens,
+            item_list,
+            time,
+        ): Self::SystemData,
+    ) {
+        for (entity, _, parent, render) in (&entities, &icons, &parents, &mut renders).join() {
+            let effects = status_effects
+                .get(parent.entity)
+                .map_or(&[][..], |status| &status.effects[..]);
+            // the sprite of the item each buff came from, and the frames it has left
+            let buffs: Vec<(usize, u32)> = effects
+                .iter()
+                .filter(|effect| effect.kind.is_buff())
+                .filter_map(|effect| Some((item_list.icon(&effect.kind)?, effect.duration)))
+                .collect();
+            if buffs.is_empty() {
+                skip_fail!(hiddens.insert(entity, Hidden));
+                continue;
+            }
+
+            let (sprite_number, remaining) =
+                buffs[(time.frame_number() / CYCLE) as usize % buffs.len()];
+            render.sprite_number = sprite_number;
+            if remaining < ENDING && remaining / 4 % 2 == 1 {
+                skip_fail!(hiddens.insert(entity, Hidden));
+            } else {
+                hiddens.remove(entity);
+            }
+        }
+    }
+}
@@ -1,7 +1,15 @@
-use amethyst::ecs::prelude::*;
+use amethyst::{
+    core::{math::*, Transform},
+    ecs::prelude::*,
+    renderer::SpriteRender,
+};
+use rand::prelude::*;
 
 use crate::audio::*;
+use crate::common::{prefab::*, vector2ext::Vector2Ext};
 use crate::components::*;
+use crate::prefab::*;
+use crate::resources::{MatchRules, Teams};
 use crate::skip_fail;
 
 pub struct BulletSystem;
@@ -10,42 +18,214 @@ impl<'s> System<'s> for BulletSystem {
         Entities<'s>,
         WriteStorage<'s, Bullet>,
         ReadStorage<'s, ColliderResult>,
+        WriteStorage<'s, Destructible>,
         ReadStorage<'s, Player>,
+        ReadStorage<'s, StatusEffects>,
+        WriteStorage<'s, Rigidbody>,
+        WriteStorage<'s, SpriteRender>,
+        ReadStorage<'s, Transform>,
+        ReadExpect<'s, MatchRules>,
+        ReadExpect<'s, Teams>,
+        RuntimePrefabLoader<'s, MyPrefabData>,
         AudioPlayer<'s>,
     );
 
-    fn run(&mut self, (entities, mut bullets, results, players, mut audio): Self::SystemData) {
+    fn run(
+        &mut self,
+        (
+            entities,
+            mut bullets,
+            results,
+            mut destructibles,
+            players,
+            status_effects,
+            mut rigidbodies,
+            mut sprites,
+            transforms,
+            rules,
+            teams,
+            mut prefab_loader,
+            mut audio,
+        ): Self::SystemData,
+    ) {
+        let mut interceptions = Vec::new();
+        // deleted entities stay alive until the end of the frame
+        let mut deleted = BitSet::new();
+
         for (entity, bullet, result) in (&entities, &mut bullets, &results).join() {
             if bullet.timer_limit != 0 {
                 bullet.timer_count += 1;
                 if bullet.timer_count > bullet.timer_limit {
                     skip_fail!(entities.delete(entity));
+                    deleted.add(entity.id());
                     continue;
                 }
             }
 
+            let mut walls_hit = Vec::new();
             for collided in &result.collided {
                 match collided.tag.as_str() {
                     "Wall" => {
+                        // the same wall can be reported twice per frame, hit it once
+                        if walls_hit.contains(&collided.entity) {
+                            continue;
+                        }
+                        walls_hit.push(collided.entity);
+                        if let Some(wall) = destructibles.get_mut(collided.entity) {
+                            wall.hit(bullet.damage);
+                        }
                         bullet.reflect_count += 1;
                         if bullet.reflect_count > bullet.reflect_limit {
                             skip_fail!(entities.delete(entity));
+                            deleted.add(entity.id());
                         }
                     }
                     "Player" => {
                         let player = skip_fail!(players
                             .get(collided.entity)
                             .ok_or("Failed to get player component"));
-                        if player.team != bullet.team {
+                        // bullets pass through a tofu that just spawned
+                        let protected = status_effects
+                            .get(collided.entity)
+                            .map_or(false, |status| status.protection().is_some());
+                        if protected {
+                            continue;
+                        }
+                        let hit = rules
+                            .friendly_fire
+                            .hit_multiplier(
+                                bullet.team,
+                                player.team,
+                                bullet.shooter == Some(collided.entity),
+                                false,
+                            )
+                            .is_some();
+                        if hit {
                             if !bullet.pierce {
                                 skip_fail!(entities.delete(entity));
+                                deleted.add(entity.id());
                             }
                             audio.play_once(entity, 3, 0.2 + bullet.damage / 25.0);
                         }
                     }
+                    "Bullet" => {
+                        if bullet.intercept != Intercept::None {
+                            interceptions.push((entity, collided.entity));
+                        }
+                    }
                     _ => {}
                 }
             }
         }
+
+        for (entity, target) in interceptions {
+            if deleted.contains(entity.id()) || deleted.contains(target.id()) {
+                continue;
+            }
+            let (team, damage, pierce, intercept, shooter, credit) = {
+                let bullet =
+                    skip_fail!(bullets.get(entity).ok_or("Failed to get bullet component"));
+                (
+                    bullet.team,
+                    bullet.damage,
+                    bullet.pierce,
+                    bullet.intercept,
+                    bullet.shooter,
+                    bullet.credit,
+                )
+            };
+            let target_bullet = skip_fail!(bullets
+                .get_mut(target)
+                .ok_or("Failed to get bullet component"));
+            if target_bullet.team == team {
+                continue;
+            }
+            let target_damage = target_bullet.damage;
+
+            match intercept {
+                Intercept::None => continue,
+                Intercept::Destroy => {
+                    target_bullet.hp -= damage;
+                    if target_bullet.hp <= 0.0 {
+                        skip_fail!(entities.delete(target));
+                        deleted.add(target.id());
+                    }
+                    if !pierce {
+                        skip_fail!(entities.delete(entity));
+                        deleted.add(entity.id());
+                    }
+                }
+                Intercept::Absorb => {
+                    skip_fail!(entities.delete(target));
+                    deleted.add(target.id());
+                }
+                Intercept::Reflect => {
+                    let old_team = target_bullet.team;
+                    target_bullet.team = team;
+                    // hits of the reflected bullet go to whoever reflected it
+                    target_bullet.shooter = shooter;
+                    target_bullet.credit = credit;
+                    target_bullet.timer_count = 0;
+                    if let Some(rigidbody) = rigidbodies.get_mut(target) {
+                        rigidbody.velocity = -rigidbody.velocity;
+                    }
+                    if let Some(sprite) = sprites.get_mut(target) {
+                        sprite.sprite_number = sprite.sprite_number - teams.sprite_offset(old_team)
+                            + teams.sprite_offset(team);
+                    }
+                }
+            }
+
+            if intercept == Intercept::Absorb || intercept == Intercept::Reflect {
+                let bullet = skip_fail!(bullets
+                    .get_mut(entity)
+                    .ok_or("Failed to get bullet component"));
+                bullet.hp -= target_damage;
+                if bullet.hp <= 0.0 {
+                    skip_fail!(entities.delete(entity));
+                    deleted.add(entity.id());
+                }
+            }
+            audio.play_once(target, 3, 0.1 + damage / 50.0);
+        }
+
+        // explode bullets that died this frame
+        for (bullet, transform, sprite, _) in (&bullets, &transforms, &sprites, &deleted).join() {
+            let explosion = match &bullet.explosion {
+                Some(explosion) => explosion,
+                None => continue,
+            };
+            let mut transform = transform.clone();
+            transform.set_rotation_euler(0.0, 0.0, 0.0);
+            prefab_loader.load_main(MyPrefabData {
+                transform: Some(transform.clone()),
+                explosion: Some(Explosion {
+                    team: bullet.team,
+                    shooter: bullet.shooter,
+                    credit: bullet.credit,
+                    ..explosion.clone()
+                }),
+                ..Default::default()
+            });
+            transform.set_scale(Vector3::new(0.5, 0.5, 1.0));
+            for _ in 0..8 {
+                prefab_loader.load_main(MyPrefabData {
+                    transform: Some(transform.clone()),
+                    rigidbody: Some(Rigidbody {
+                        velocity: Vector2::from_polar(
+                            explosion.radius / 8.0,
+                            random::<f32>() * f32::two_pi(),
+                        ),
+                        drag: 0.1,
+                        ..Default::default()
+                    }),
+                    sprite: Some(SpriteRenderPrefab {
+                        sprite_number: sprite.sprite_number,
+                    }),
+                    particle: Some(Particle { timer: 12 }),
+                    ..Default::default()
+                });
+            }
+        }
     }
 }
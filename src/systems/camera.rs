@@ -1,4 +1,4 @@
-use amethyst::{core::Transform, ecs::prelude::*, renderer::Camera};
+use amethyst::{core::math::*, core::Transform, ecs::prelude::*, renderer::Camera};
 
 use crate::common::pause::Pause;
 use crate::components::*;
@@ -11,7 +11,7 @@ pub struct CameraSystem {
 impl<'s> System<'s> for CameraSystem {
     type SystemData = (
         Entities<'s>,
-        ReadStorage<'s, Camera>,
+        WriteStorage<'s, Camera>,
         WriteStorage<'s, Transform>,
         ReadStorage<'s, Playable>,
         ReadStorage<'s, Player>,
@@ -21,7 +21,7 @@ impl<'s> System<'s> for CameraSystem {
 
     fn run(
         &mut self,
-        (entities, cameras, mut transforms, playables, players, areas, pause): Self::SystemData,
+        (entities, mut cameras, mut transforms, playables, players, areas, pause): Self::SystemData,
     ) {
         if pause.paused() {
             if let Some((_, transform)) = (&areas, &transforms).join().next() {
@@ -44,11 +44,33 @@ impl<'s> System<'s> for CameraSystem {
             }
         }
 
+        // frame every human player, zooming out when they drift apart. there is no split
+        // screen, past MAX_ZOOM the players furthest from the center leave the view
+        let humans: Vec<(Entity, Vector2<f32>)> = (&entities, &playables, &transforms)
+            .join()
+            .map(|(entity, _, transform)| (entity, transform.translation().xy()))
+            .collect();
+        if let Some(&(first, first_pos)) = humans.first() {
+            // keep following the first human for a moment when everyone is dead
+            self.timer = 0;
+            self.target_entity = Some(first);
+            let min = humans.iter().fold(first_pos, |a, (_, b)| a.inf(b));
+            let max = humans.iter().fold(first_pos, |a, (_, b)| a.sup(b));
+            let center = (min + max) / 2.0;
+            let half_width = ((max.x - min.x) / 2.0 + MARGIN)
+                .max(((max.y - min.y) / 2.0 + MARGIN) * 4.0 / 3.0)
+                .max(HALF_WIDTH)
+                .min(HALF_WIDTH * MAX_ZOOM);
+            for (camera, transform) in (&mut cameras, &mut transforms).join() {
+                transform.set_translation_x(center.x);
+                transform.set_translation_y(center.y);
+                zoom_to(camera, half_width);
+            }
+            return;
+        }
+
         let target_entity = {
-            if let Some((entity, _)) = (&entities, &playables).join().next() {
-                self.timer = 0;
-                entity
-            } else if let Some(entity) = self.target_entity {
+            if let Some(entity) = self.target_entity {
                 entity
             } else if self.timer > 0 {
                 return;
@@ -70,9 +92,26 @@ impl<'s> System<'s> for CameraSystem {
             }
         };
 
-        for (transform, _) in (&mut transforms, &cameras).join() {
+        for (camera, transform) in (&mut cameras, &mut transforms).join() {
             transform.set_translation_x(target_pos.x);
             transform.set_translation_y(target_pos.y);
+            zoom_to(camera, HALF_WIDTH);
         }
     }
 }
+
+// half of the visible width at normal zoom, see resources/camera.ron
+const HALF_WIDTH: f32 = 160.0;
+const MAX_ZOOM: f32 = 2.0;
+const MARGIN: f32 = 64.0;
+
+fn zoom_to(camera: &mut Camera, half_width: f32) {
+    if let Some(ortho) = camera.projection_mut().as_orthographic_mut() {
+        let current = (ortho.right() - ortho.left()) / 2.0;
+        let half_width = current + (half_width - current) / 10.0;
+        let half_height = half_width * 3.0 / 4.0;
+        ortho.set_left_and_right(-half_width, half_width);
+        // Y axis reversed
+        ortho.set_bottom_and_top(half_height, -half_height);
+    }
+}
@@ -0,0 +1,62 @@
+use amethyst::{
+    ecs::prelude::*,
+    ui::{UiFinder, UiText},
+};
+
+use crate::components::AreaPath;
+use crate::resources::{ClockPhase, MatchClock, MatchRules, Score};
+
+pub struct MatchClockSystem;
+impl<'s> System<'s> for MatchClockSystem {
+    type SystemData = (
+        WriteExpect<'s, MatchClock>,
+        ReadExpect<'s, Score>,
+        ReadExpect<'s, MatchRules>,
+        UiFinder<'s>,
+        WriteStorage<'s, UiText>,
+        ReadStorage<'s, AreaPath>,
+    );
+
+    fn run(&mut self, (mut clock, score, rules, finder, mut texts, paths): Self::SystemData) {
+        if let Some(text) = finder
+            .find("clock_text")
+            .and_then(|entity| texts.get_mut(entity))
+        {
+            text.text = clock.text();
+        }
+
+        let clock = &mut *clock;
+        if !clock.running || clock.winner.is_some() {
+            return;
+        }
+        // the team whose side the area is on, unless it is contested
+        let leader = score
+            .leader(AreaPath::held_on(&paths))
+            .filter(|_| !score.contested);
+
+        match clock.phase {
+            ClockPhase::Regular => match clock.remaining {
+                Some(0) => {
+                    if leader.is_some() {
+                        clock.winner = leader;
+                    } else {
+                        clock.phase = if rules.clock.sudden_death {
+                            ClockPhase::SuddenDeath
+                        } else {
+                            ClockPhase::Overtime
+                        };
+                        clock.overtime_score = score.score.clone();
+                    }
+                }
+                Some(ref mut remaining) => *remaining -= 1,
+                None => {}
+            },
+            ClockPhase::Overtime | ClockPhase::SuddenDeath => {
+                if score.score != clock.overtime_score {
+                    clock.winner = leader;
+                    clock.overtime_score = score.score.clone();
+                }
+            }
+        }
+    }
+}
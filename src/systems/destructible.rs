@@ -0,0 +1,81 @@
+use amethyst::{
+    core::{math::*, Transform},
+    ecs::prelude::*,
+    renderer::SpriteRender,
+};
+use rand::prelude::*;
+
+use crate::audio::*;
+use crate::common::{prefab::*, vector2ext::Vector2Ext};
+use crate::components::*;
+use crate::prefab::*;
+use crate::skip_fail;
+
+// shows the damage bullets and explosions did to walls and breaks the worn out ones
+pub struct DestructibleSystem;
+impl<'s> System<'s> for DestructibleSystem {
+    type SystemData = (
+        Entities<'s>,
+        ReadStorage<'s, Destructible>,
+        ReadStorage<'s, RectCollider>,
+        ReadStorage<'s, Transform>,
+        WriteStorage<'s, SpriteRender>,
+        RuntimePrefabLoader<'s, MyPrefabData>,
+        AudioPlayer<'s>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            destructibles,
+            colliders,
+            transforms,
+            mut sprites,
+            mut prefab_loader,
+            mut audio,
+        ): Self::SystemData,
+    ) {
+        for (entity, wall, collider, transform) in
+            (&entities, &destructibles, &colliders, &transforms).join()
+        {
+            if !wall.broken() {
+                if let (Some(sprite_number), Some(sprite)) =
+                    (wall.damage_sprite(), sprites.get_mut(entity))
+                {
+                    sprite.sprite_number = sprite_number;
+                }
+                continue;
+            }
+
+            // the debris comes off all over the wall, in the sprite it last had
+            let center = transform.translation().xy();
+            let sprite_number = sprites.get(entity).map_or(0, |sprite| sprite.sprite_number);
+            for _ in 0..wall.debris {
+                let offset = Vector2::new(
+                    (random::<f32>() - 0.5) * collider.width,
+                    (random::<f32>() - 0.5) * collider.height,
+                );
+                let mut transform = Transform::default();
+                transform.set_translation_xyz(center.x + offset.x, center.y + offset.y, 0.0);
+                transform.set_scale(Vector3::new(0.5, 0.5, 1.0));
+                prefab_loader.load_main(MyPrefabData {
+                    transform: Some(transform),
+                    rigidbody: Some(Rigidbody {
+                        velocity: Vector2::from_polar(
+                            1.0 + random::<f32>() * 2.0,
+                            random::<f32>() * f32::two_pi(),
+                        ),
+                        drag: 0.1,
+                        ..Default::default()
+                    }),
+                    sprite: Some(SpriteRenderPrefab { sprite_number }),
+                    particle: Some(Particle { timer: 20 }),
+                    ..Default::default()
+                });
+            }
+            audio.play_once(entity, 3, 1.0);
+            skip_fail!(entities.delete(entity));
+        }
+    }
+}
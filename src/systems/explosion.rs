@@ -0,0 +1,105 @@
+use amethyst::{
+    core::{math::*, Transform},
+    ecs::prelude::*,
+};
+
+use crate::audio::*;
+use crate::components::*;
+use crate::resources::{MatchClock, MatchRules, Stats};
+use crate::skip_fail;
+
+pub struct ExplosionSystem;
+impl<'s> System<'s> for ExplosionSystem {
+    type SystemData = (
+        Entities<'s>,
+        ReadStorage<'s, Explosion>,
+        WriteStorage<'s, Player>,
+        ReadStorage<'s, Transform>,
+        WriteStorage<'s, Rigidbody>,
+        ReadStorage<'s, StatusEffects>,
+        WriteStorage<'s, Destructible>,
+        ReadStorage<'s, RectCollider>,
+        ReadExpect<'s, MatchRules>,
+        WriteExpect<'s, Stats>,
+        AudioPlayer<'s>,
+        ReadExpect<'s, MatchClock>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            explosions,
+            mut players,
+            transforms,
+            mut rigidbodies,
+            status_effects,
+            mut destructibles,
+            colliders,
+            rules,
+            mut stats,
+            mut audio,
+            clock,
+        ): Self::SystemData,
+    ) {
+        for (entity, explosion, transform) in (&entities, &explosions, &transforms).join() {
+            let center = transform.translation().xy();
+            for (victim, player, p_transform, rigidbody, status) in (
+                &entities,
+                &mut players,
+                &transforms,
+                &mut rigidbodies,
+                status_effects.maybe(),
+            )
+                .join()
+            {
+                let dist = p_transform.translation().xy() - center;
+                if dist.norm() > explosion.radius {
+                    continue;
+                }
+                if status.map_or(false, |status| status.protection().is_some()) {
+                    continue;
+                }
+                let hit_multiplier = match rules.friendly_fire.hit_multiplier(
+                    explosion.team,
+                    player.team,
+                    explosion.shooter == Some(victim),
+                    true,
+                ) {
+                    Some(multiplier) => multiplier,
+                    None => continue,
+                };
+                let damage_multiplier = status.map_or(1.0, |status| status.damage_multiplier())
+                    * clock.damage_multiplier(&rules.clock);
+                let damage = explosion.damage * hit_multiplier * damage_multiplier;
+                player.hp -= damage;
+                if let Some(credit) = explosion.credit {
+                    player.last_hit = Some(credit);
+                    // friendly fire hurts but is not counted
+                    if explosion.team != player.team {
+                        stats.entry(credit.combatant).damage_dealt += damage;
+                    }
+                }
+                rigidbody.acceleration += dist.try_normalize(0.0).unwrap_or(Vector2::zeros())
+                    * explosion.knockback
+                    * hit_multiplier;
+            }
+            // walls are hit where they are closest to the center
+            for (wall, w_transform, collider) in
+                (&mut destructibles, &transforms, &colliders).join()
+            {
+                let half = Vector2::new(collider.width, collider.height) / 2.0;
+                let offset = center - w_transform.translation().xy();
+                let closest = Vector2::new(
+                    offset.x.max(-half.x).min(half.x),
+                    offset.y.max(-half.y).min(half.y),
+                );
+                if (offset - closest).norm() <= explosion.radius {
+                    wall.hit(explosion.damage);
+                }
+            }
+            audio.play_once(entity, 3, 0.2 + explosion.damage / 25.0);
+            skip_fail!(entities.delete(entity));
+        }
+    }
+}
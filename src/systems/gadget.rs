@@ -0,0 +1,138 @@
+use amethyst::{
+    core::{math::*, Transform},
+    ecs::prelude::*,
+};
+
+use crate::audio::*;
+use crate::components::*;
+
+// zones keep their effect up for as long as the player stays in them
+const ZONE_TICKS: u32 = 2;
+
+#[derive(Default)]
+pub struct GadgetSystem {
+    // players that came out of a teleporter, it does not send them back until they step off
+    arrived: Vec<(Entity, Entity)>,
+}
+impl<'s> System<'s> for GadgetSystem {
+    type SystemData = (
+        Entities<'s>,
+        ReadStorage<'s, Gadget>,
+        ReadStorage<'s, ColliderResult>,
+        ReadStorage<'s, Player>,
+        WriteStorage<'s, Transform>,
+        WriteStorage<'s, Rigidbody>,
+        WriteStorage<'s, StatusEffects>,
+        AudioPlayer<'s>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            gadgets,
+            results,
+            players,
+            mut transforms,
+            mut rigidbodies,
+            mut status_effects,
+            mut audio,
+        ): Self::SystemData,
+    ) {
+        let touching = |player: Entity, gadget: Entity| {
+            results.get(gadget).map_or(false, |result| {
+                result.collided.iter().any(|c| c.entity == player)
+            })
+        };
+        self.arrived
+            .retain(|&(player, teleporter)| touching(player, teleporter));
+
+        let mut teleports = Vec::new();
+        for (entity, gadget, result) in (&entities, &gadgets, &results).join() {
+            let center = match transforms.get(entity) {
+                Some(transform) => transform.translation().xy(),
+                None => continue,
+            };
+            for collided in result.collided.iter().filter(|c| c.tag == "Player") {
+                let player = collided.entity;
+                if !players.contains(player) {
+                    continue;
+                }
+                let protected = status_effects
+                    .get(player)
+                    .map_or(false, |status| status.protection().is_some());
+
+                match gadget.kind {
+                    GadgetKind::DamageZone(damage) if !protected => {
+                        if let Some(status) = status_effects.get_mut(player) {
+                            status.refresh(StatusEffect {
+                                kind: StatusKind::DamageOverTime(damage),
+                                duration: ZONE_TICKS,
+                            });
+                        }
+                    }
+                    GadgetKind::SlowZone(value) => {
+                        if let Some(status) = status_effects.get_mut(player) {
+                            status.refresh(StatusEffect {
+                                kind: StatusKind::Slow(value),
+                                duration: ZONE_TICKS,
+                            });
+                        }
+                    }
+                    GadgetKind::SpeedPad(x, y) => {
+                        if let Some(rigidbody) = rigidbodies.get_mut(player) {
+                            rigidbody.acceleration += Vector2::new(x, y);
+                        }
+                    }
+                    GadgetKind::BouncePad(strength) => {
+                        let position = match transforms.get(player) {
+                            Some(transform) => transform.translation().xy(),
+                            None => continue,
+                        };
+                        if let Some(rigidbody) = rigidbodies.get_mut(player) {
+                            rigidbody.acceleration += (position - center)
+                                .try_normalize(0.0)
+                                .unwrap_or(Vector2::zeros())
+                                * strength;
+                        }
+                    }
+                    GadgetKind::Teleporter(channel) => {
+                        if self
+                            .arrived
+                            .iter()
+                            .any(|&(p, t)| p == player && t == entity)
+                        {
+                            continue;
+                        }
+                        let exit = (&entities, &gadgets).join().find(|(other, gadget)| {
+                            *other != entity && gadget.kind == GadgetKind::Teleporter(channel)
+                        });
+                        if let Some((exit, _)) = exit {
+                            teleports.push((player, exit));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        for (player, exit) in teleports {
+            // a player on two teleporters at once only goes through one
+            if self.arrived.iter().any(|&(p, _)| p == player) {
+                continue;
+            }
+            let target = match transforms.get(exit) {
+                Some(transform) => *transform.translation(),
+                None => continue,
+            };
+            if let Some(transform) = transforms.get_mut(player) {
+                transform.set_translation_xyz(target.x, target.y, transform.translation().z);
+            }
+            if let Some(rigidbody) = rigidbodies.get_mut(player) {
+                rigidbody.velocity = Vector2::zeros();
+            }
+            self.arrived.push((player, exit));
+            audio.play_once(player, 3, 0.5);
+        }
+    }
+}
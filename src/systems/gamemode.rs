@@ -0,0 +1,240 @@
+use amethyst::{
+    core::{math::*, Hidden, Transform},
+    ecs::prelude::*,
+    shrev::{EventChannel, ReaderId},
+    ui::{UiFinder, UiText},
+};
+
+use crate::components::*;
+use crate::resources::{GameMode, MatchRules, PlayerKilled, Score, Series, SeriesRules, Teams};
+use crate::skip_fail;
+
+#[derive(Default)]
+pub struct DeathmatchSystem {
+    reader: Option<ReaderId<PlayerKilled>>,
+}
+impl<'s> System<'s> for DeathmatchSystem {
+    type SystemData = (
+        Read<'s, EventChannel<PlayerKilled>>,
+        WriteExpect<'s, Score>,
+        ReadExpect<'s, GameMode>,
+    );
+
+    fn setup(&mut self, world: &mut World) {
+        Self::SystemData::setup(world);
+        self.reader = Some(
+            world
+                .fetch_mut::<EventChannel<PlayerKilled>>()
+                .register_reader(),
+        );
+    }
+
+    fn run(&mut self, (channel, mut score, mode): Self::SystemData) {
+        let reader = self.reader.as_mut().expect("Failed to get ReaderId??");
+        for event in channel.read(reader) {
+            if let Some(killer) = event.killer {
+                mode.score_kill(&mut score, killer.team(), event.victim.team());
+            }
+        }
+    }
+}
+
+pub struct FlagSystem;
+impl<'s> System<'s> for FlagSystem {
+    type SystemData = (
+        Entities<'s>,
+        WriteStorage<'s, Flag>,
+        ReadStorage<'s, Player>,
+        ReadStorage<'s, ColliderResult>,
+        WriteStorage<'s, Transform>,
+        WriteExpect<'s, Score>,
+        ReadExpect<'s, GameMode>,
+        ReadExpect<'s, MatchRules>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            mut flags,
+            players,
+            results,
+            mut transforms,
+            mut score,
+            mode,
+            rules,
+        ): Self::SystemData,
+    ) {
+        if !mode.uses_flags() {
+            return;
+        }
+
+        let flag_entities: Vec<Entity> = (&entities, &flags).join().map(|(e, _)| e).collect();
+        let mut captured = Vec::new();
+
+        for &entity in flag_entities.iter() {
+            let carrier_pos = flags
+                .get(entity)
+                .and_then(|flag| flag.carrier)
+                .filter(|&carrier| players.get(carrier).is_some())
+                .and_then(|carrier| transforms.get(carrier))
+                .map(|transform| *transform.translation());
+            let carried: Vec<Entity> = flags.join().filter_map(|flag| flag.carrier).collect();
+            let flag = skip_fail!(flags.get_mut(entity).ok_or("Failed to get flag component"));
+            let transform = skip_fail!(transforms
+                .get_mut(entity)
+                .ok_or("Failed to get transform component"));
+            if flag.home.is_none() {
+                flag.home = Some(*transform.translation());
+            }
+            let home = flag.home.unwrap_or_else(Vector3::zeros);
+
+            // the flag rides on its carrier and is dropped where the carrier died
+            match carrier_pos {
+                Some(pos) => transform.set_translation_xyz(pos.x, pos.y, 1.0),
+                None if flag.carrier.is_some() => {
+                    flag.carrier = None;
+                    flag.dropped = 0;
+                }
+                None => {}
+            }
+            let at_home = flag.carrier.is_none() && transform.translation().xy() == home.xy();
+            if flag.carrier.is_none() && !at_home {
+                flag.dropped += 1;
+                if flag.dropped >= rules.modes.flag_return {
+                    send_home(flag, transform);
+                    continue;
+                }
+            }
+            if flag.carrier.is_some() {
+                continue;
+            }
+
+            let result = match results.get(entity) {
+                Some(result) => result,
+                None => continue,
+            };
+            for collided in result.collided.iter() {
+                let player = match players.get(collided.entity) {
+                    Some(player) => player,
+                    None => continue,
+                };
+                if player.team != flag.team {
+                    if !carried.contains(&collided.entity) {
+                        flag.carrier = Some(collided.entity);
+                        break;
+                    }
+                } else if !at_home {
+                    send_home(flag, transform);
+                    break;
+                } else if carried.contains(&collided.entity) {
+                    captured.push((player.team, collided.entity));
+                    break;
+                }
+            }
+        }
+
+        for (team, carrier) in captured {
+            for (flag, transform) in (&mut flags, &mut transforms).join() {
+                if flag.carrier == Some(carrier) {
+                    send_home(flag, transform);
+                    mode.score_capture(&mut score, team);
+                }
+            }
+        }
+    }
+}
+
+pub fn send_home(flag: &mut Flag, transform: &mut Transform) {
+    flag.carrier = None;
+    flag.dropped = 0;
+    if let Some(home) = flag.home {
+        transform.set_translation(home);
+    }
+}
+
+// shows only the objectives the mode uses, maps can be reloaded at any time
+pub struct ObjectiveVisibilitySystem;
+impl<'s> System<'s> for ObjectiveVisibilitySystem {
+    type SystemData = (
+        Entities<'s>,
+        ReadStorage<'s, Area>,
+        ReadStorage<'s, AreaTarget>,
+        ReadStorage<'s, Flag>,
+        WriteStorage<'s, Hidden>,
+        ReadExpect<'s, GameMode>,
+    );
+
+    fn run(&mut self, (entities, areas, targets, flags, mut hidden, mode): Self::SystemData) {
+        for (entity, area, target, flag) in
+            (&entities, areas.maybe(), targets.maybe(), flags.maybe()).join()
+        {
+            let shown = match (area, target, flag) {
+                (Some(_), _, _) => mode.uses_area(),
+                (_, Some(_), _) => *mode == GameMode::Area,
+                (_, _, Some(_)) => mode.uses_flags(),
+                _ => continue,
+            };
+            if shown {
+                hidden.remove(entity);
+            } else if !hidden.contains(entity) {
+                skip_fail!(hidden.insert(entity, Hidden));
+            }
+        }
+    }
+}
+
+// score line for the modes that do not show the score with the area,
+// and the round of the series
+pub struct ScoreTextSystem;
+impl<'s> System<'s> for ScoreTextSystem {
+    type SystemData = (
+        ReadExpect<'s, Score>,
+        ReadExpect<'s, Teams>,
+        ReadExpect<'s, GameMode>,
+        ReadExpect<'s, MatchRules>,
+        ReadExpect<'s, Series>,
+        ReadExpect<'s, SeriesRules>,
+        UiFinder<'s>,
+        WriteStorage<'s, UiText>,
+    );
+
+    fn run(
+        &mut self,
+        (score, teams, mode, rules, series, series_rules, finder, mut texts): Self::SystemData,
+    ) {
+        if let Some(text) = finder
+            .find("score_text")
+            .and_then(|entity| texts.get_mut(entity))
+        {
+            text.text = match mode.target(&rules) {
+                Some(target) => {
+                    let scores: Vec<String> = teams
+                        .iter()
+                        .zip(score.score.iter())
+                        .map(|(team, score)| format!("{} {}", team.name.to_uppercase(), score))
+                        .collect();
+                    format!("{}  / {}", scores.join("  "), target)
+                }
+                None => String::new(),
+            };
+        }
+
+        if let Some(text) = finder
+            .find("round_text")
+            .and_then(|entity| texts.get_mut(entity))
+        {
+            text.text = if series_rules.best_of > 1 {
+                let wins: Vec<String> = series.wins.iter().map(|wins| wins.to_string()).collect();
+                format!(
+                    "ROUND {}/{}  {}",
+                    series.round,
+                    series_rules.best_of,
+                    wins.join(" - ")
+                )
+            } else {
+                String::new()
+            };
+        }
+    }
+}
@@ -10,10 +10,21 @@ impl<'s> System<'s> for ItemSystem {
         WriteStorage<'s, Item>,
         ReadStorage<'s, ColliderResult>,
         WriteStorage<'s, Player>,
+        WriteStorage<'s, StatusEffects>,
         WriteStorage<'s, Hidden>,
     );
 
-    fn run(&mut self, (entities, mut items, results, mut players, mut hidden): Self::SystemData) {
+    fn run(
+        &mut self,
+        (
+            entities,
+            mut items,
+            results,
+            mut players,
+            mut status_effects,
+            mut hidden,
+        ): Self::SystemData,
+    ) {
         for (entity, mut item, result) in (&entities, &mut items, &results).join() {
             item.timer -= 1;
             if item.timer < 120 {
@@ -40,7 +51,16 @@ impl<'s> System<'s> for ItemSystem {
                             let player = skip_fail!(players
                                 .get_mut(collided.entity)
                                 .ok_or("Failed to get player component"));
-                            player.hp = (player.hp + item.hp).min(100.0);
+                            let mut status = status_effects.get_mut(collided.entity);
+                            if let (Some(kind), Some(status)) = (item.kind.status(), &mut status) {
+                                status.refresh(StatusEffect {
+                                    kind,
+                                    duration: item.duration,
+                                });
+                            }
+                            // an overcharge raises the cap before its hp is added
+                            let max_hp = status.map_or(100.0, |status| status.max_hp());
+                            player.hp = (player.hp + item.kind.hp()).min(max_hp.max(player.hp));
                         }
                         _ => {}
                     }
@@ -1,20 +1,38 @@
 pub mod area;
+pub mod buff;
 pub mod bullet;
 pub mod camera;
+pub mod clock;
+pub mod destructible;
+pub mod explosion;
+pub mod gadget;
+pub mod gamemode;
 pub mod item;
+pub mod navgrid;
 pub mod particle;
 pub mod player;
 pub mod reticle;
 pub mod shield;
+pub mod stats;
+pub mod status;
 
 pub use area::*;
+pub use buff::*;
 pub use bullet::*;
 pub use camera::*;
+pub use clock::*;
+pub use destructible::*;
+pub use explosion::*;
+pub use gadget::*;
+pub use gamemode::*;
 pub use item::*;
+pub use navgrid::*;
 pub use particle::*;
 pub use player::*;
 pub use reticle::*;
 pub use shield::*;
+pub use stats::*;
+pub use status::*;
 
 pub use crate::common::{
     collision2d::{CollisionSystem, RigidbodySystem},
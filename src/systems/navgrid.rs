@@ -0,0 +1,68 @@
+use amethyst::{
+    core::{math::Vector2, Transform},
+    ecs::prelude::*,
+};
+
+use crate::components::*;
+use crate::resources::{NavGrid, NavRect};
+
+// builds the NavGrid again whenever walls or gadgets come, like with a new map,
+// or go, like a broken wall
+#[derive(Default)]
+pub struct NavGridSystem {
+    built_from: Vec<Entity>,
+}
+impl<'s> System<'s> for NavGridSystem {
+    type SystemData = (
+        Entities<'s>,
+        ReadStorage<'s, RectCollider>,
+        ReadStorage<'s, Gadget>,
+        ReadStorage<'s, Transform>,
+        Write<'s, NavGrid>,
+    );
+
+    fn run(&mut self, (entities, colliders, gadgets, transforms, mut nav_grid): Self::SystemData) {
+        let objects: Vec<_> = (&entities, &colliders, &transforms, gadgets.maybe())
+            .join()
+            .filter(|(_, collider, _, gadget)| collider.tag == "Wall" || gadget.is_some())
+            .collect();
+        let built_from: Vec<Entity> = objects.iter().map(|object| object.0).collect();
+        if built_from == self.built_from {
+            return;
+        }
+        self.built_from = built_from;
+
+        let rect = |collider: &RectCollider, transform: &Transform| -> NavRect {
+            (
+                transform.translation().xy(),
+                Vector2::new(collider.width, collider.height),
+            )
+        };
+        let walls: Vec<NavRect> = objects
+            .iter()
+            .filter(|(_, collider, _, _)| collider.tag == "Wall")
+            .map(|(_, collider, transform, _)| rect(collider, transform))
+            .collect();
+        let hazards: Vec<(NavRect, u32)> = objects
+            .iter()
+            .filter_map(|(_, collider, transform, gadget)| {
+                let cost = hazard_cost(&gadget.as_ref()?.kind);
+                if cost > 0 {
+                    Some((rect(collider, transform), cost))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        *nav_grid = NavGrid::new(&walls, &hazards);
+    }
+}
+
+// what stepping into a cell of the hazard costs the AI on top of the 10 of a plain step
+fn hazard_cost(kind: &GadgetKind) -> u32 {
+    match *kind {
+        GadgetKind::DamageZone(damage) => 50 + (damage * 100.0) as u32,
+        GadgetKind::SlowZone(value) => (value * 40.0) as u32,
+        _ => 0,
+    }
+}
@@ -4,6 +4,8 @@ use amethyst::{
     core::Transform,
     ecs::prelude::*,
     input::{InputHandler, StringBindings},
+    renderer::Camera,
+    shrev::EventChannel,
     window::ScreenDimensions,
 };
 use rand::{distributions::*, prelude::*};
@@ -12,76 +14,89 @@ use crate::audio::*;
 use crate::common::{prefab::*, vector2ext::Vector2Ext};
 use crate::components::*;
 use crate::prefab::*;
-use crate::resources::WeaponList;
+use crate::resources::{
+    ItemList, LocalPlayers, MatchClock, MatchRules, PlayerKilled, Stats, Teams, WeaponList,
+};
 use crate::skip_fail;
 
+#[derive(Default)]
 pub struct PlayableSystem {
     before_mouse: Vector2<f32>,
-    use_mouse: bool,
-}
-impl Default for PlayableSystem {
-    fn default() -> Self {
-        PlayableSystem {
-            before_mouse: Vector2::zeros(),
-            use_mouse: false,
-        }
-    }
+    use_mouse: Vec<bool>,
 }
 impl<'s> System<'s> for PlayableSystem {
     type SystemData = (
         Read<'s, InputHandler<StringBindings>>,
         ReadExpect<'s, ScreenDimensions>,
-        WriteStorage<'s, Playable>,
+        ReadExpect<'s, LocalPlayers>,
+        ReadStorage<'s, Camera>,
+        ReadStorage<'s, Transform>,
+        ReadStorage<'s, Playable>,
         WriteStorage<'s, Player>,
     );
 
-    fn run(&mut self, (input, screen, mut playables, mut players): Self::SystemData) {
-        let axis_xy_value = |x: &str, y: &str| {
-            Some(Vector2::new(
-                input.axis_value(x)? as f32,
-                input.axis_value(y)? as f32,
-            ))
-        };
-
-        let move_vec = axis_xy_value("move_x", "move_y").unwrap_or(Vector2::zeros());
-        let dpad_vec = axis_xy_value("dpad_x", "dpad_y").unwrap_or(Vector2::zeros());
-        let left_vec = axis_xy_value("left_x", "left_y").unwrap_or(Vector2::zeros());
-        let move_vec = move_vec + dpad_vec + left_vec;
-        let (move_r, move_theta) = move_vec.to_polar();
-        let move_vec = Vector2::from_polar(move_r.min(1.0), move_theta);
+    fn run(
+        &mut self,
+        (
+            input,
+            screen,
+            local_players,
+            cameras,
+            transforms,
+            playables,
+            mut players,
+        ): Self::SystemData,
+    ) {
+        self.use_mouse.resize(local_players.len(), false);
 
-        let aim_vec = axis_xy_value("right_x", "right_y").unwrap_or(Vector2::zeros());
-        let (aim_r, _) = aim_vec.to_polar();
+        // mouse position in world coordinates, the camera may be zoomed out
+        let mouse_pos = input.mouse_position().and_then(|v| {
+            let (camera, transform) = (&cameras, &transforms).join().next()?;
+            let ortho = camera.projection().as_orthographic()?;
+            Some(
+                transform.translation().xy()
+                    + Vector2::new(
+                        (v.0 as f32 / screen.width() - 0.5) * (ortho.right() - ortho.left()),
+                        (v.1 as f32 / screen.height() - 0.5) * (ortho.bottom() - ortho.top()),
+                    ),
+            )
+        });
         let mouse_vec = input
             .mouse_position()
-            .map(|v| {
-                Vector2::new(
-                    v.0 as f32 - (screen.width() / 2.0),
-                    v.1 as f32 - (screen.height() / 2.0),
-                )
-            })
+            .map(|v| Vector2::new(v.0 as f32, v.1 as f32))
             .unwrap_or(Vector2::zeros());
-
         let move_mouse = self.before_mouse != mouse_vec;
-        if move_mouse {
-            self.use_mouse = true;
-        }
-
         self.before_mouse = mouse_vec;
 
-        let aim_vec = if aim_r > 0.1 {
-            self.use_mouse = false;
-            aim_vec
-        } else if self.use_mouse {
-            mouse_vec
-        } else {
-            move_vec
-        };
+        for (playable, player, transform) in (&playables, &mut players, &transforms).join() {
+            let local = skip_fail!(local_players
+                .get(playable.id)
+                .ok_or("Failed to get local player"));
+            let use_mouse = &mut self.use_mouse[playable.id];
+
+            let move_vec = local.move_input(&input);
+            let (move_r, move_theta) = move_vec.to_polar();
+            let move_vec = Vector2::from_polar(move_r.min(1.0), move_theta);
+
+            let aim_vec = local.aim_input(&input);
+            let (aim_r, _) = aim_vec.to_polar();
+
+            if move_mouse && local.uses_mouse() {
+                *use_mouse = true;
+            }
+
+            let aim_vec = if aim_r > 0.1 {
+                *use_mouse = false;
+                aim_vec
+            } else if *use_mouse {
+                mouse_pos.map_or(Vector2::zeros(), |pos| pos - transform.translation().xy())
+            } else {
+                move_vec
+            };
 
-        let shot = input.action_is_down("shot").unwrap_or(false);
-        let hold = input.action_is_down("hold").unwrap_or(false);
+            let shot = local.shot(&input);
+            let hold = local.hold(&input);
 
-        for (_, player) in (&mut playables, &mut players).join() {
             player.input_move = move_vec;
             if !hold {
                 let (aim_r, aim_theta) = aim_vec.to_polar();
@@ -100,27 +115,53 @@ impl<'s> System<'s> for PlayerControlSystem {
         RuntimePrefabLoader<'s, MyPrefabData>,
         AudioPlayer<'s>,
         ReadExpect<'s, WeaponList>,
+        ReadExpect<'s, Teams>,
         (
             Entities<'s>,
             WriteStorage<'s, Player>,
             ReadStorage<'s, Transform>,
             WriteStorage<'s, Rigidbody>,
+            ReadStorage<'s, StatusEffects>,
+            ReadStorage<'s, Playable>,
         ),
     );
 
-    fn run(&mut self, (mut prefab_loader, mut audio, weapon_list, storages): Self::SystemData) {
-        let (entities, mut players, transforms, mut rigidbodies) = storages;
+    fn run(
+        &mut self,
+        (mut prefab_loader, mut audio, weapon_list, teams, storages): Self::SystemData,
+    ) {
+        let (entities, mut players, transforms, mut rigidbodies, status_effects, playables) =
+            storages;
 
-        for (entity, player, transform, rigidbody) in
-            (&entities, &mut players, &transforms, &mut rigidbodies).join()
+        for (entity, player, transform, rigidbody, status, playable) in (
+            &entities,
+            &mut players,
+            &transforms,
+            &mut rigidbodies,
+            status_effects.maybe(),
+            playables.maybe(),
+        )
+            .join()
         {
-            let weapon = &weapon_list[player.weapon];
+            // an item may have swapped the weapon for a while
+            let weapon_index = status
+                .and_then(|status| status.weapon())
+                .unwrap_or(player.weapon);
+            let weapon = weapon_list.weapon(weapon_index);
+
+            let stunned = status.map_or(false, |status| status.stunned());
+            let move_multiplier = status.map_or(1.0, |status| status.move_multiplier());
 
-            let move_vec = player.input_move;
+            let move_vec = if stunned {
+                Vector2::zeros()
+            } else {
+                player.input_move
+            };
             let aim_vec = player.input_aim;
-            let shot = player.input_shot;
+            let protected = status.map_or(false, |status| status.protection().is_some());
+            let shot = player.input_shot && !stunned && !protected;
 
-            rigidbody.acceleration = move_vec * weapon.move_speed;
+            rigidbody.acceleration = move_vec * weapon.move_speed * move_multiplier;
 
             if player.trigger_timer > 0 {
                 player.trigger_timer -= 1;
@@ -137,6 +178,17 @@ impl<'s> System<'s> for PlayerControlSystem {
                 let mut bullet_transform = transform.clone();
                 bullet_transform.set_translation_z(-1.0);
 
+                let credit = Credit {
+                    combatant: Combatant::of(player, playable),
+                    weapon: weapon_index,
+                };
+                let mut bullet = Bullet::new(entity, player.team, credit, weapon);
+                let attack_multiplier = status.map_or(1.0, |status| status.attack_multiplier());
+                bullet.damage *= attack_multiplier;
+                if let Some(explosion) = &mut bullet.explosion {
+                    explosion.damage *= attack_multiplier;
+                }
+
                 prefab_loader.load_main(MyPrefabData {
                     transform: Some(bullet_transform),
                     rigidbody: Some(Rigidbody {
@@ -148,26 +200,19 @@ impl<'s> System<'s> for PlayerControlSystem {
                         ..Default::default()
                     }),
                     sprite: Some(SpriteRenderPrefab {
-                        sprite_number: weapon.bullet_sprite + player.team as usize,
+                        sprite_number: weapon.bullet_sprite + teams.sprite_offset(player.team),
                     }),
                     collider: Some(RectCollider::new(
                         "Bullet",
                         weapon.bullet_collider.0,
                         weapon.bullet_collider.1,
                     )),
-                    bullet: Some(Bullet::new(
-                        player.team,
-                        weapon.bullet_damage,
-                        weapon.bullet_timer_limit,
-                        weapon.bullet_reflect_limit,
-                        weapon.bullet_knockback,
-                        weapon.bullet_slowing,
-                        weapon.bullet_pierce,
-                    )),
+                    bullet: Some(bullet),
                     ..Default::default()
                 });
-                player.trigger_timer = weapon.rate;
-                audio.play_once(entity, player.weapon, 0.4);
+                let rate_multiplier = status.map_or(1.0, |status| status.rate_multiplier());
+                player.trigger_timer = (weapon.rate as f32 * rate_multiplier).round() as u32;
+                audio.play_once(entity, weapon.shot_sound, 0.4);
             }
         }
     }
@@ -176,30 +221,89 @@ impl<'s> System<'s> for PlayerControlSystem {
 pub struct PlayerCollisionSystem;
 impl<'s> System<'s> for PlayerCollisionSystem {
     type SystemData = (
+        Entities<'s>,
         WriteStorage<'s, Player>,
         ReadStorage<'s, Bullet>,
         ReadStorage<'s, Transform>,
         WriteStorage<'s, Rigidbody>,
         ReadStorage<'s, ColliderResult>,
+        WriteStorage<'s, StatusEffects>,
+        ReadExpect<'s, MatchRules>,
+        WriteExpect<'s, Stats>,
+        ReadExpect<'s, MatchClock>,
     );
 
     fn run(
         &mut self,
-        (mut players, bullets, transforms, mut rigidbodies, results): Self::SystemData,
+        (
+            entities,
+            mut players,
+            bullets,
+            transforms,
+            mut rigidbodies,
+            results,
+            mut status_effects,
+            rules,
+            mut stats,
+            clock,
+        ): Self::SystemData,
     ) {
-        for (player, transform, rigidbody, result) in
-            (&mut players, &transforms, &mut rigidbodies, &results).join()
+        for (entity, player, transform, rigidbody, result, mut status) in (
+            &entities,
+            &mut players,
+            &transforms,
+            &mut rigidbodies,
+            &results,
+            (&mut status_effects).maybe(),
+        )
+            .join()
         {
+            let mut affected_by = Vec::new();
             for collided in &result.collided {
                 match collided.tag.as_str() {
                     "Bullet" => {
                         let bullet = skip_fail!(bullets
                             .get(collided.entity)
                             .ok_or("Failed to get bullet component"));
-                        if bullet.team == player.team {
+                        if status.as_ref().map_or(false, |s| s.protection().is_some()) {
                             continue;
                         }
-                        player.hp -= bullet.damage;
+                        let hit_multiplier = match rules.friendly_fire.hit_multiplier(
+                            bullet.team,
+                            player.team,
+                            bullet.shooter == Some(entity),
+                            false,
+                        ) {
+                            Some(multiplier) => multiplier,
+                            None => continue,
+                        };
+                        let damage_multiplier = status
+                            .as_ref()
+                            .map_or(1.0, |status| status.damage_multiplier())
+                            * clock.damage_multiplier(&rules.clock);
+                        let damage = bullet.damage * hit_multiplier * damage_multiplier;
+                        player.hp -= damage;
+                        // the same bullet can be reported twice per frame, apply its effects once
+                        let first_report = !affected_by.contains(&collided.entity);
+                        if first_report {
+                            affected_by.push(collided.entity);
+                            if let Some(status) = status.as_mut() {
+                                for effect in &bullet.effects {
+                                    status.add(effect.clone());
+                                }
+                            }
+                        }
+                        if let Some(credit) = bullet.credit {
+                            player.last_hit = Some(credit);
+                            // friendly fire hurts but is not counted
+                            if bullet.team != player.team {
+                                let attacker = stats.entry(credit.combatant);
+                                attacker.damage_dealt += damage;
+                                if first_report {
+                                    attacker.shots_hit += 1;
+                                }
+                            }
+                        }
                         let b_pos = skip_fail!(transforms
                             .get(collided.entity)
                             .ok_or("Failed to get transform component"))
@@ -210,7 +314,9 @@ impl<'s> System<'s> for PlayerCollisionSystem {
                         rigidbody.velocity *= 1.0 - bullet.slowing;
                         rigidbody.acceleration *= 1.0 - bullet.slowing;
                         rigidbody.acceleration +=
-                            dist.try_normalize(0.0).unwrap_or(Vector2::zeros()) * bullet.knockback;
+                            dist.try_normalize(0.0).unwrap_or(Vector2::zeros())
+                                * bullet.knockback
+                                * hit_multiplier;
                     }
                     _ => {}
                 }
@@ -228,18 +334,38 @@ impl<'s> System<'s> for PlayerDeathSystem {
         WriteExpect<'s, ParentHierarchy>,
         RuntimePrefabLoader<'s, MyPrefabData>,
         AudioPlayer<'s>,
+        ReadStorage<'s, Playable>,
+        Write<'s, EventChannel<PlayerKilled>>,
+        ReadExpect<'s, MatchClock>,
+        ReadExpect<'s, ItemList>,
     );
 
     fn run(
         &mut self,
-        (entities, players, tramsforms, hierarchy, mut prefab_loader, mut audio): Self::SystemData,
+        (
+            entities,
+            players,
+            tramsforms,
+            hierarchy,
+            mut prefab_loader,
+            mut audio,
+            playables,
+            mut killed_events,
+            clock,
+            item_list,
+        ): Self::SystemData,
     ) {
         use rand::prelude::*;
 
-        for (entity, player) in (&entities, &players).join() {
+        for (entity, player, playable) in (&entities, &players, playables.maybe()).join() {
             if player.hp > 0.0 {
                 continue;
             }
+            killed_events.single_write(PlayerKilled {
+                victim: Combatant::of(player, playable),
+                killer: player.last_hit.map(|credit| credit.combatant),
+                weapon: player.last_hit.map(|credit| credit.weapon),
+            });
             skip_fail!(entities.delete(entity));
             for entity in hierarchy.all_children_iter(entity) {
                 skip_fail!(entities.delete(entity));
@@ -248,7 +374,12 @@ impl<'s> System<'s> for PlayerDeathSystem {
                 .get(entity)
                 .ok_or("Failed to get transform component"))
             .clone();
-            for _ in 0..10 {
+            let drops = if clock.item_drops() {
+                item_list.roll_drops()
+            } else {
+                Vec::new()
+            };
+            for item_type in drops {
                 prefab_loader.load_main(MyPrefabData {
                     transform: Some(transform.clone()),
                     rigidbody: Some(Rigidbody {
@@ -257,10 +388,13 @@ impl<'s> System<'s> for PlayerDeathSystem {
                         bounciness: 0.8,
                         ..Default::default()
                     }),
-                    sprite: Some(SpriteRenderPrefab { sprite_number: 15 }),
+                    sprite: Some(SpriteRenderPrefab {
+                        sprite_number: item_type.sprite,
+                    }),
                     collider: Some(RectCollider::new("Item", 4.0, 4.0)),
                     item: Some(Item {
-                        hp: 10.0,
+                        kind: item_type.kind.clone(),
+                        duration: item_type.duration,
                         timer: 300,
                     }),
                     ..Default::default()
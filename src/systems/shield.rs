@@ -1,4 +1,4 @@
-use amethyst::{core::transform::*, ecs::prelude::*, renderer::SpriteRender};
+use amethyst::{core::transform::*, core::Hidden, ecs::prelude::*, renderer::SpriteRender};
 
 use crate::components::*;
 use crate::skip_fail;
@@ -6,17 +6,47 @@ use crate::skip_fail;
 pub struct ShieldSystem;
 impl<'s> System<'s> for ShieldSystem {
     type SystemData = (
+        Entities<'s>,
         ReadStorage<'s, Shield>,
         WriteStorage<'s, SpriteRender>,
         ReadStorage<'s, Parent>,
         ReadStorage<'s, Player>,
+        ReadStorage<'s, StatusEffects>,
+        WriteStorage<'s, Hidden>,
     );
 
-    fn run(&mut self, (shields, mut renders, parents, players): Self::SystemData) {
-        for (_, parent, render) in (&shields, &parents, &mut renders).join() {
+    fn run(
+        &mut self,
+        (
+            entities,
+            shields,
+            mut renders,
+            parents,
+            players,
+            status_effects,
+            mut hiddens,
+        ): Self::SystemData,
+    ) {
+        for (entity, _, parent, render) in (&entities, &shields, &parents, &mut renders).join() {
             let player = skip_fail!(players
                 .get(parent.entity)
                 .ok_or("Failed to get player component"));
+
+            // a blinking full shield while spawn protected
+            let protection = status_effects
+                .get(parent.entity)
+                .and_then(|status| status.protection());
+            if let Some(remaining) = protection {
+                render.sprite_number = 10;
+                if remaining / 4 % 2 == 0 {
+                    hiddens.remove(entity);
+                } else {
+                    skip_fail!(hiddens.insert(entity, Hidden));
+                }
+                continue;
+            }
+            hiddens.remove(entity);
+
             let hp = player.hp;
             if hp >= 100.0 {
                 render.sprite_number = 10;
@@ -0,0 +1,99 @@
+use amethyst::{
+    ecs::prelude::*,
+    shrev::{EventChannel, ReaderId},
+    ui::{UiFinder, UiText},
+};
+use std::collections::VecDeque;
+
+use crate::resources::{stats, PlayerKilled, Stats, Teams, WeaponList};
+
+#[derive(Default)]
+pub struct StatsSystem {
+    reader: Option<ReaderId<PlayerKilled>>,
+}
+impl<'s> System<'s> for StatsSystem {
+    type SystemData = (Read<'s, EventChannel<PlayerKilled>>, WriteExpect<'s, Stats>);
+
+    fn setup(&mut self, world: &mut World) {
+        Self::SystemData::setup(world);
+        self.reader = Some(
+            world
+                .fetch_mut::<EventChannel<PlayerKilled>>()
+                .register_reader(),
+        );
+    }
+
+    fn run(&mut self, (channel, mut stats): Self::SystemData) {
+        let reader = self.reader.as_mut().expect("Failed to get ReaderId??");
+        for event in channel.read(reader) {
+            stats.entry(event.victim).deaths += 1;
+            // no kill for friendly fire or suicide
+            if let Some(killer) = event.killer {
+                if killer.team() != event.victim.team() {
+                    stats.entry(killer).kills += 1;
+                }
+            }
+        }
+    }
+}
+
+const FEED_LINES: usize = 5;
+const FEED_TIME: u32 = 300;
+
+#[derive(Default)]
+pub struct KillFeedSystem {
+    reader: Option<ReaderId<PlayerKilled>>,
+    lines: VecDeque<(String, u32)>,
+}
+impl<'s> System<'s> for KillFeedSystem {
+    type SystemData = (
+        Read<'s, EventChannel<PlayerKilled>>,
+        ReadExpect<'s, Teams>,
+        ReadExpect<'s, WeaponList>,
+        UiFinder<'s>,
+        WriteStorage<'s, UiText>,
+    );
+
+    fn setup(&mut self, world: &mut World) {
+        Self::SystemData::setup(world);
+        self.reader = Some(
+            world
+                .fetch_mut::<EventChannel<PlayerKilled>>()
+                .register_reader(),
+        );
+    }
+
+    fn run(&mut self, (channel, teams, weapon_list, finder, mut texts): Self::SystemData) {
+        let reader = self.reader.as_mut().expect("Failed to get ReaderId??");
+        for event in channel.read(reader) {
+            let victim = stats::name(event.victim, &teams);
+            let line = match (event.killer, event.weapon) {
+                (Some(killer), Some(weapon)) => format!(
+                    "{} [{}] {}",
+                    stats::name(killer, &teams),
+                    weapon_list.weapon(weapon).name,
+                    victim
+                ),
+                _ => format!("{} fell", victim),
+            };
+            self.lines.push_front((line, FEED_TIME));
+        }
+        for (_, timer) in self.lines.iter_mut() {
+            *timer = timer.saturating_sub(1);
+        }
+        self.lines.retain(|(_, timer)| *timer > 0);
+        self.lines.truncate(FEED_LINES);
+
+        for i in 0..FEED_LINES {
+            if let Some(text) = finder
+                .find(&format!("killfeed{}", i + 1))
+                .and_then(|entity| texts.get_mut(entity))
+            {
+                text.text = self
+                    .lines
+                    .get(i)
+                    .map_or(String::new(), |(line, _)| line.clone());
+            }
+        }
+    }
+}
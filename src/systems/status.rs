@@ -0,0 +1,28 @@
+use amethyst::ecs::prelude::*;
+
+use crate::components::*;
+use crate::resources::{MatchClock, MatchRules};
+
+pub struct StatusEffectSystem;
+impl<'s> System<'s> for StatusEffectSystem {
+    type SystemData = (
+        WriteStorage<'s, Player>,
+        WriteStorage<'s, StatusEffects>,
+        ReadExpect<'s, MatchRules>,
+        ReadExpect<'s, MatchClock>,
+    );
+
+    fn run(&mut self, (mut players, mut status_effects, rules, clock): Self::SystemData) {
+        let clock_multiplier = clock.damage_multiplier(&rules.clock);
+        for (player, status) in (&mut players, &mut status_effects).join() {
+            // nothing hurts a tofu that just spawned
+            if status.protection().is_none() {
+                player.hp -=
+                    status.damage_per_tick() * status.damage_multiplier() * clock_multiplier;
+            }
+            status.tick();
+            // what an overcharge gave is lost when it runs out
+            player.hp = player.hp.min(status.max_hp());
+        }
+    }
+}
@@ -0,0 +1,270 @@
+use serde::de::DeserializeOwned;
+use serde_derive::Deserialize;
+use std::fmt;
+
+use crate::common::ronfile::{self, RonError};
+use crate::resources::Teams;
+
+pub trait Validate {
+    fn validate(&self, v: &mut Validator);
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct DataContext {
+    pub sprite_count: usize,
+    // sprites that come in one colour per team are offset by these
+    pub team_sprite_offsets: Vec<usize>,
+    // teams with spawn points of their own in resources/teams.ron, maps may leave them out
+    pub team_fallback_spawns: Vec<bool>,
+    // entries in resources/weapon_list.ron, items can swap to any of them
+    pub weapon_count: usize,
+}
+
+impl DataContext {
+    pub fn new(sprite_count: usize) -> Self {
+        DataContext {
+            sprite_count,
+            team_sprite_offsets: Vec::new(),
+            team_fallback_spawns: Vec::new(),
+            weapon_count: 0,
+        }
+    }
+
+    pub fn set_teams(&mut self, teams: &Teams) {
+        self.team_sprite_offsets = teams.iter().map(|team| team.sprite_offset).collect();
+        self.team_fallback_spawns = teams
+            .iter()
+            .map(|team| !team.spawn_points.is_empty())
+            .collect();
+    }
+}
+
+pub struct Validator {
+    context: DataContext,
+    problems: Vec<String>,
+    warnings: Vec<String>,
+}
+
+impl Validator {
+    pub fn new(context: &DataContext) -> Self {
+        Validator {
+            context: context.clone(),
+            problems: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    pub fn context(&self) -> &DataContext {
+        &self.context
+    }
+
+    pub fn check(&mut self, ok: bool, message: impl Into<String>) {
+        if !ok {
+            self.problems.push(message.into());
+        }
+    }
+
+    // likely a mistake but playable, logged when the data is loaded
+    pub fn warn(&mut self, ok: bool, message: impl Into<String>) {
+        if !ok {
+            self.warnings.push(message.into());
+        }
+    }
+
+    pub fn problems(&self) -> &[String] {
+        &self.problems
+    }
+
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    pub fn non_negative(&mut self, field: &str, value: f32) {
+        self.check(
+            value >= 0.0,
+            format!("{}: must not be negative (got {})", field, value),
+        );
+    }
+
+    pub fn positive(&mut self, field: &str, value: f32) {
+        self.check(
+            value > 0.0,
+            format!("{}: must be greater than zero (got {})", field, value),
+        );
+    }
+
+    pub fn sprite(&mut self, field: &str, index: usize) {
+        let sprite_count = self.context.sprite_count;
+        self.check(
+            index < sprite_count,
+            format!(
+                "{}: sprite {} does not exist in texture/spritesheet.ron ({} sprites)",
+                field, index, sprite_count
+            ),
+        );
+    }
+
+    pub fn team_sprite(&mut self, field: &str, index: usize) {
+        let mut offsets = self.context.team_sprite_offsets.clone();
+        offsets.sort();
+        offsets.dedup();
+        for offset in offsets {
+            self.sprite(field, index + offset);
+        }
+    }
+
+    pub fn team(&mut self, field: &str, team: u32) {
+        let team_count = self.context.team_sprite_offsets.len();
+        self.check(
+            (team as usize) < team_count,
+            format!(
+                "{}: team {} does not exist in resources/teams.ron ({} teams)",
+                field, team, team_count
+            ),
+        );
+    }
+
+    // a share taken off something, like the time between shots
+    pub fn fraction(&mut self, field: &str, value: f32) {
+        self.check(
+            value >= 0.0 && value < 1.0,
+            format!("{}: must be at least 0 and below 1 (got {})", field, value),
+        );
+    }
+
+    pub fn weapon(&mut self, field: &str, index: usize) {
+        let weapon_count = self.context.weapon_count;
+        self.check(
+            index < weapon_count,
+            format!(
+                "{}: weapon {} does not exist in resources/weapon_list.ron ({} weapons)",
+                field, index, weapon_count
+            ),
+        );
+    }
+
+    pub fn finish(self, source: &str) -> Result<(), ValidationError> {
+        for warning in self.warnings.iter() {
+            log::warn!("{}: {}", source, warning);
+        }
+        if self.problems.is_empty() {
+            Ok(())
+        } else {
+            Err(ValidationError {
+                source: source.to_string(),
+                problems: self.problems,
+            })
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ValidationError {
+    pub source: String,
+    pub problems: Vec<String>,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} is invalid:", self.source)?;
+        for problem in &self.problems {
+            write!(f, "\n  - {}", problem)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+#[derive(Debug)]
+pub enum DataError {
+    Ron(RonError),
+    Invalid(ValidationError),
+}
+
+impl fmt::Display for DataError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DataError::Ron(e) => e.fmt(f),
+            DataError::Invalid(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for DataError {}
+
+impl From<RonError> for DataError {
+    fn from(e: RonError) -> Self {
+        DataError::Ron(e)
+    }
+}
+
+impl From<ValidationError> for DataError {
+    fn from(e: ValidationError) -> Self {
+        DataError::Invalid(e)
+    }
+}
+
+pub fn load<T>(path: &str, context: &DataContext) -> Result<T, DataError>
+where
+    T: DeserializeOwned + Validate,
+{
+    let data: T = ronfile::load(path)?;
+    check(&data, path, context)?;
+    Ok(data)
+}
+
+pub fn load_bytes<T>(bytes: &[u8], name: &str, context: &DataContext) -> Result<T, DataError>
+where
+    T: DeserializeOwned + Validate,
+{
+    let data: T = ronfile::load_bytes(bytes, name)?;
+    check(&data, name, context)?;
+    Ok(data)
+}
+
+pub fn check<T: Validate>(
+    data: &T,
+    source: &str,
+    context: &DataContext,
+) -> Result<(), ValidationError> {
+    let mut v = Validator::new(context);
+    data.validate(&mut v);
+    v.finish(source)
+}
+
+#[derive(Deserialize)]
+#[allow(dead_code)]
+struct SpritePositionData {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+#[derive(Deserialize)]
+#[allow(dead_code)]
+struct SpriteListData {
+    texture_width: u32,
+    texture_height: u32,
+    sprites: Vec<SpritePositionData>,
+}
+
+#[derive(Deserialize)]
+enum SpriteSheetData {
+    List(SpriteListData),
+}
+
+pub fn sprite_count() -> Result<usize, RonError> {
+    #[cfg(feature = "include_resources")]
+    let sheet: SpriteSheetData = ronfile::load_bytes(
+        include_bytes!("../texture/spritesheet.ron"),
+        "texture/spritesheet.ron",
+    )?;
+    #[cfg(not(feature = "include_resources"))]
+    let sheet: SpriteSheetData = ronfile::load("texture/spritesheet.ron")?;
+
+    match sheet {
+        SpriteSheetData::List(list) => Ok(list.sprites.len()),
+    }
+}